@@ -0,0 +1,232 @@
+//! 声明式断言, 在每次响应后求值
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::HttpResponse;
+
+/// 断言类型
+#[derive(Debug, Default, strum::AsRefStr, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AssertionKind {
+    /// 状态码等于 value
+    #[default]
+    StatusEquals,
+    /// 状态码在 value 范围内, 如 `200-299` 或 `2xx`
+    StatusInRange,
+    /// 响应头 target 存在
+    HeaderExists,
+    /// 响应头 target 匹配正则 value
+    HeaderMatches,
+    /// 响应体包含 value
+    BodyContains,
+    /// JSON 路径 target 的值等于 value
+    JsonPathEquals,
+    /// 响应时间小于 value (ms)
+    ResponseTimeBelow,
+    /// 响应体不超过 value 字节
+    BodySizeBelow,
+    /// 响应体不少于 value 字节
+    BodySizeAbove,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Assertion {
+    pub kind: AssertionKind,
+    /// 响应头名称或 JSON 路径
+    pub target: String,
+    /// 期望值
+    pub value: String,
+    pub disable: bool,
+}
+
+/// 断言结果
+#[derive(Debug, Clone)]
+pub struct AssertionResult {
+    pub name: String,
+    pub passed: bool,
+    pub message: String,
+}
+
+impl Assertion {
+    pub fn new(kind: AssertionKind, target: &str, value: &str) -> Self {
+        Self {
+            kind,
+            target: target.to_owned(),
+            value: value.to_owned(),
+            disable: false,
+        }
+    }
+
+    /// 断言的可读描述
+    pub fn describe(&self) -> String {
+        let Assertion { target, value, .. } = self;
+        match self.kind {
+            AssertionKind::StatusEquals => format!("status == {}", value),
+            AssertionKind::StatusInRange => format!("status in {}", value),
+            AssertionKind::HeaderExists => format!("header {} exists", target),
+            AssertionKind::HeaderMatches => format!("header {} ~ /{}/", target, value),
+            AssertionKind::BodyContains => format!("body contains {:?}", value),
+            AssertionKind::JsonPathEquals => format!("{} == {}", target, value),
+            AssertionKind::ResponseTimeBelow => format!("time < {} ms", value),
+            AssertionKind::BodySizeBelow => format!("size <= {} B", value),
+            AssertionKind::BodySizeAbove => format!("size >= {} B", value),
+        }
+    }
+
+    pub fn evaluate(&self, response: &HttpResponse) -> AssertionResult {
+        let (passed, message) = match self.check(response) {
+            Ok(message) => (true, message),
+            Err(message) => (false, message),
+        };
+        AssertionResult {
+            name: self.describe(),
+            passed,
+            message,
+        }
+    }
+
+    /// 成功返回 Ok(实际值), 失败返回 Err(原因)
+    fn check(&self, response: &HttpResponse) -> Result<String, String> {
+        let value = self.value.trim();
+        match self.kind {
+            AssertionKind::StatusEquals => {
+                let expected = parse_number(value)?;
+                let actual = response.status.as_u16() as u64;
+                compare(actual == expected, format!("status {}", actual))
+            }
+            AssertionKind::StatusInRange => {
+                let (min, max) = parse_status_range(value)?;
+                let actual = response.status.as_u16() as u64;
+                compare((min..=max).contains(&actual), format!("status {}", actual))
+            }
+            AssertionKind::HeaderExists => match header_value(response, &self.target) {
+                Some(v) => Ok(format!("{}: {}", self.target, v)),
+                None => Err(format!("缺少响应头 {}", self.target)),
+            },
+            AssertionKind::HeaderMatches => {
+                let re = Regex::new(value).map_err(|e| format!("正则错误: {}", e))?;
+                match header_value(response, &self.target) {
+                    Some(v) => compare(re.is_match(&v), format!("{}: {}", self.target, v)),
+                    None => Err(format!("缺少响应头 {}", self.target)),
+                }
+            }
+            AssertionKind::BodyContains => {
+                let body = body_text(response);
+                compare(body.contains(&self.value), format!("body {} B", body.len()))
+            }
+            AssertionKind::JsonPathEquals => {
+                let body = body_text(response);
+                let json: Value =
+                    serde_json::from_str(&body).map_err(|e| format!("响应体不是 JSON: {}", e))?;
+                let actual = json_path(&json, &self.target)
+                    .ok_or_else(|| format!("路径不存在 {}", self.target))?;
+                let actual_str = json_to_plain(actual);
+                let expected = serde_json::from_str::<Value>(value)
+                    .map(|v| json_to_plain(&v))
+                    .unwrap_or_else(|_| value.to_owned());
+                compare(actual_str == expected, actual_str)
+            }
+            AssertionKind::ResponseTimeBelow => {
+                let max = parse_number(value)?;
+                let actual = response.duration;
+                compare(actual < max as u128, format!("{} ms", actual))
+            }
+            AssertionKind::BodySizeBelow => {
+                let max = parse_number(value)?;
+                let actual = response.response_size;
+                compare(actual <= max, format!("{} B", actual))
+            }
+            AssertionKind::BodySizeAbove => {
+                let min = parse_number(value)?;
+                let actual = response.response_size;
+                compare(actual >= min, format!("{} B", actual))
+            }
+        }
+    }
+}
+
+/// 对所有启用的断言求值
+pub fn evaluate_all(assertions: &[Assertion], response: &HttpResponse) -> Vec<AssertionResult> {
+    assertions
+        .iter()
+        .filter(|a| !a.disable)
+        .map(|a| a.evaluate(response))
+        .collect()
+}
+
+/// 简单 JSON 路径: `$.data.items[0].id` 或 `data.items.0.id`
+pub fn json_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+
+    let mut current = value;
+    for segment in path.split('.').filter(|s| !s.is_empty()) {
+        let (name, indexes) = match segment.find('[') {
+            Some(i) => segment.split_at(i),
+            None => (segment, ""),
+        };
+
+        if !name.is_empty() {
+            current = match current {
+                Value::Object(obj) => obj.get(name)?,
+                Value::Array(arr) => arr.get(name.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+
+        for index in indexes.split('[').filter(|s| !s.is_empty()) {
+            let index: usize = index.strip_suffix(']')?.trim().parse().ok()?;
+            current = current.as_array()?.get(index)?;
+        }
+    }
+    Some(current)
+}
+
+fn compare(passed: bool, actual: String) -> Result<String, String> {
+    if passed {
+        Ok(actual)
+    } else {
+        Err(format!("实际: {}", actual))
+    }
+}
+
+fn parse_number(value: &str) -> Result<u64, String> {
+    value
+        .parse::<u64>()
+        .map_err(|_| format!("期望值不是数字: {}", value))
+}
+
+fn parse_status_range(value: &str) -> Result<(u64, u64), String> {
+    if let Some(prefix) = value.strip_suffix("xx").or_else(|| value.strip_suffix("XX")) {
+        let n = parse_number(prefix)?;
+        return Ok((n * 100, n * 100 + 99));
+    }
+    match value.split_once('-') {
+        Some((min, max)) => Ok((parse_number(min.trim())?, parse_number(max.trim())?)),
+        None => Err(format!("范围格式错误, 应为 200-299 或 2xx: {}", value)),
+    }
+}
+
+fn header_value(response: &HttpResponse, name: &str) -> Option<String> {
+    response
+        .headers
+        .get(name.trim())
+        .map(|v| v.to_str().unwrap_or("").to_owned())
+}
+
+fn body_text(response: &HttpResponse) -> String {
+    response
+        .data_vec
+        .as_ref()
+        .map(|d| String::from_utf8_lossy(d).into_owned())
+        .unwrap_or_default()
+}
+
+/// 字符串去掉引号, 其他类型按 JSON 输出
+fn json_to_plain(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.to_owned(),
+        v => v.to_string(),
+    }
+}
@@ -0,0 +1,320 @@
+use api_test_rs::report::{self, LoadReport, Thresholds};
+use api_test_rs::runner::{self, RunFilter};
+use api_test_rs::{util, PairUi};
+use clap::{Parser, Subcommand};
+
+/// api-test-rs 无界面运行器
+#[derive(Parser)]
+#[command(name = "api-test-cli", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 按顺序运行项目中的测试, 有失败时退出码非 0
+    Run {
+        /// 项目文件路径 (.json)
+        project: String,
+
+        /// 仅运行指定组, 可重复
+        #[arg(short, long = "group")]
+        groups: Vec<String>,
+
+        /// 仅运行指定测试 (`test` 或 `group/test`), 可重复
+        #[arg(short, long = "test")]
+        tests: Vec<String>,
+
+        /// 使用的环境名, 默认为项目保存时选中的环境
+        #[arg(short, long = "env")]
+        env: Option<String>,
+
+        /// 覆盖项目变量 (key=value), 可重复, 优先级最高
+        #[arg(long = "var", value_parser = runner::parse_var_override)]
+        vars: Vec<PairUi>,
+    },
+    /// 按测试保存的批量发送设置运行压测, 与基线相比退化时退出码为 1
+    Load {
+        /// 项目文件路径 (.json)
+        project: String,
+
+        /// 要运行的测试 (`test` 或 `group/test`)
+        #[arg(short, long)]
+        test: String,
+
+        /// 使用的环境名, 默认为项目保存时选中的环境
+        #[arg(short, long = "env")]
+        env: Option<String>,
+
+        /// 覆盖项目变量 (key=value), 可重复, 优先级最高
+        #[arg(long = "var", value_parser = runner::parse_var_override)]
+        vars: Vec<PairUi>,
+
+        /// 导出结果, 扩展名为 .csv 时写为 CSV, 否则为 JSON, 可重复
+        #[arg(short, long = "out")]
+        outputs: Vec<String>,
+
+        /// 基线结果 (JSON)
+        #[arg(short, long)]
+        baseline: Option<String>,
+
+        /// 平均响应时间允许增加的百分比
+        #[arg(long)]
+        max_avg_increase: Option<f64>,
+
+        /// P95 允许增加的百分比
+        #[arg(long)]
+        max_p95_increase: Option<f64>,
+
+        /// P99 允许增加的百分比
+        #[arg(long)]
+        max_p99_increase: Option<f64>,
+
+        /// QPS 允许下降的百分比
+        #[arg(long)]
+        max_qps_decrease: Option<f64>,
+
+        /// 错误率允许增加的百分点
+        #[arg(long)]
+        max_error_rate_increase: Option<f64>,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+
+    let code = match cli.command {
+        Command::Run {
+            project,
+            groups,
+            tests,
+            env,
+            vars,
+        } => run(&project, RunFilter { groups, tests }, env.as_deref(), &vars).await,
+        Command::Load {
+            project,
+            test,
+            env,
+            vars,
+            outputs,
+            baseline,
+            max_avg_increase,
+            max_p95_increase,
+            max_p99_increase,
+            max_qps_decrease,
+            max_error_rate_increase,
+        } => {
+            let defaults = Thresholds::default();
+            let thresholds = Thresholds {
+                avg_increase: max_avg_increase.unwrap_or(defaults.avg_increase),
+                p95_increase: max_p95_increase.unwrap_or(defaults.p95_increase),
+                p99_increase: max_p99_increase.unwrap_or(defaults.p99_increase),
+                qps_decrease: max_qps_decrease.unwrap_or(defaults.qps_decrease),
+                error_rate_increase: max_error_rate_increase.unwrap_or(defaults.error_rate_increase),
+            };
+            load(&project, &test, env.as_deref(), &vars, &outputs, baseline.as_deref(), &thresholds).await
+        }
+    };
+
+    std::process::exit(code);
+}
+
+async fn run(project_path: &str, filter: RunFilter, env: Option<&str>, vars: &[PairUi]) -> i32 {
+    let mut project = match util::load_project(project_path) {
+        Ok(project) => project,
+        Err(err) => {
+            eprintln!("加载项目失败 {}: {}", project_path, err);
+            return 2;
+        }
+    };
+
+    if let Some(env) = env {
+        if let Err(err) = project.select_environment(env) {
+            eprintln!("{}", err);
+            return 2;
+        }
+    }
+
+    let report = runner::run_project(&project, &filter, vars, |outcome| {
+        let result = match (&outcome.error, outcome.status) {
+            (Some(err), _) => err.to_owned(),
+            (None, Some(status)) => format!("{} {}ms", status, outcome.duration),
+            (None, None) => String::new(),
+        };
+        println!(
+            "[{}] {} / {}  {}",
+            if outcome.passed() { "PASS" } else { "FAIL" },
+            outcome.group,
+            outcome.test,
+            result
+        );
+        for assertion in outcome.assertions.iter().filter(|r| !r.passed) {
+            println!("    ✗ {}  {}", assertion.name, assertion.message);
+        }
+    })
+    .await;
+
+    println!(
+        "\n总计: {}  通过: {}  失败: {} (断言失败: {})  跳过: {}",
+        report.outcomes.len(),
+        report.passed(),
+        report.failed(),
+        report.assertion_failed(),
+        report.skipped
+    );
+
+    if report.outcomes.is_empty() {
+        eprintln!("没有匹配的测试");
+        return 2;
+    }
+
+    if report.success() {
+        0
+    } else {
+        1
+    }
+}
+
+async fn load(
+    project_path: &str,
+    test: &str,
+    env: Option<&str>,
+    vars: &[PairUi],
+    outputs: &[String],
+    baseline: Option<&str>,
+    thresholds: &Thresholds,
+) -> i32 {
+    let mut project = match util::load_project(project_path) {
+        Ok(project) => project,
+        Err(err) => {
+            eprintln!("加载项目失败 {}: {}", project_path, err);
+            return 2;
+        }
+    };
+
+    if let Some(env) = env {
+        if let Err(err) = project.select_environment(env) {
+            eprintln!("{}", err);
+            return 2;
+        }
+    }
+
+    // 先读取基线, 避免压测结束后才发现文件有误
+    let baseline = match baseline.map(LoadReport::load).transpose() {
+        Ok(baseline) => baseline,
+        Err(err) => {
+            eprintln!("{:#}", err);
+            return 2;
+        }
+    };
+
+    let filter = RunFilter {
+        tests: vec![test.to_owned()],
+        ..Default::default()
+    };
+    let Some((group, http_test)) = project
+        .groups
+        .iter()
+        .flat_map(|g| g.childrent.iter().map(move |t| (g, t)))
+        .find(|(g, t)| filter.match_test(g, t))
+    else {
+        eprintln!("没有匹配的测试: {}", test);
+        return 2;
+    };
+
+    let mut variables = project.resolved_variables();
+    util::merge_vars(&mut variables, vars);
+
+    println!("运行 {} ({})", http_test.name, http_test.load_mode.as_ref());
+    let finished = match runner::run_load(
+        http_test,
+        project.compile_scripts(group, &http_test.request),
+        &variables,
+        project.dir.as_deref(),
+    )
+    .await {
+        Ok(finished) => finished,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 2;
+        }
+    };
+
+    for iteration in &finished.iterations {
+        println!(
+            "[{}] 第 {} 行  {}",
+            if iteration.passed() { "PASS" } else { "FAIL" },
+            iteration.row + 1,
+            match (&iteration.error, iteration.status) {
+                (Some(err), _) => err.to_owned(),
+                (None, Some(status)) => format!("{} {}ms", status, iteration.duration),
+                (None, None) => String::new(),
+            }
+        );
+        for assertion in iteration.assertions.iter().filter(|r| !r.passed) {
+            println!("    ✗ {}  {}", assertion.name, assertion.message);
+        }
+    }
+
+    let current = LoadReport::from_stats(&http_test.name, &finished.stats);
+    let s = &current.summary;
+    let ms = |v: Option<f64>| v.map(|v| format!("{:.2}ms", v)).unwrap_or_else(|| "-".to_owned());
+    println!(
+        "请求: {}  成功: {}  失败: {}  QPS: {}  耗时: {}",
+        s.requests,
+        s.success,
+        s.failed,
+        s.qps.map(|q| format!("{:.1}", q)).unwrap_or_else(|| "-".to_owned()),
+        s.duration.map(|d| format!("{:.2}s", d)).unwrap_or_else(|| "-".to_owned()),
+    );
+    println!(
+        "响应时间  Min: {}  Avg: {}  P50: {}  P95: {}  P99: {}  Max: {}",
+        ms(s.min_ms),
+        ms(s.avg_ms),
+        ms(s.p50_ms),
+        ms(s.p95_ms),
+        ms(s.p99_ms),
+        ms(s.max_ms)
+    );
+    if let Some(script) = finished.stats.avg_script_time() {
+        println!("脚本耗时  Avg: {:.3}ms  总计: {:.3}s", script, finished.stats.script_time.as_secs_f64());
+    }
+    for (kind, count) in &current.errors {
+        println!("错误  {}: {}", kind, count);
+    }
+
+    for path in outputs {
+        match current.save(path) {
+            Ok(_) => println!("已导出到 {}", path),
+            Err(err) => {
+                eprintln!("{:#}", err);
+                return 2;
+            }
+        }
+    }
+
+    let Some(baseline) = baseline else {
+        return 0;
+    };
+    println!("\n与基线对比 ({})", baseline.created_at);
+    let diffs = report::compare(&baseline, &current, thresholds);
+    let value = |v: Option<f64>| v.map(|v| format!("{:.2}", v)).unwrap_or_else(|| "-".to_owned());
+    for diff in &diffs {
+        println!(
+            "[{}] {:<12} {:>12} -> {:<12} {}",
+            if diff.regressed { "FAIL" } else { " OK " },
+            diff.name,
+            value(diff.baseline),
+            value(diff.current),
+            diff.change_text()
+        );
+    }
+
+    if diffs.iter().any(|d| d.regressed) {
+        1
+    } else {
+        0
+    }
+}
@@ -0,0 +1,510 @@
+//! 从 curl 命令行导入请求
+
+use anyhow::{bail, Result};
+use base64::{engine::general_purpose, Engine as _};
+
+use crate::{HttpRequestConfig, HttpTest, Method, PairUi, RequestBodyRawType, RequestBodyTab};
+
+/// -d 系列参数
+struct DataPart {
+    value: String,
+    /// --data-urlencode 的内容未编码
+    raw: bool,
+}
+
+/// 解析 curl 命令, 生成以 `METHOD /path` 命名的测试
+pub fn import_curl(cmd: &str) -> Result<HttpTest> {
+    let request = parse_curl(cmd)?;
+    let path = request
+        .url
+        .split_once("://")
+        .map(|(_, rest)| rest.find('/').map(|i| &rest[i..]).unwrap_or("/"))
+        .unwrap_or(&request.url);
+    let mut test = HttpTest::from_name(format!("{} {}", request.method.as_ref(), path));
+    test.request = request;
+    Ok(test)
+}
+
+/// 解析 curl 命令为请求配置
+///
+/// 支持 -X, -H, -d/--data-raw/--data-binary/--data-urlencode, -F, -u, -G, -I, -T, -A, -b, -e,
+/// URL 中的查询参数会拆分到 query; --compressed 等不影响请求内容的参数被忽略
+pub fn parse_curl(cmd: &str) -> Result<HttpRequestConfig> {
+    let args = split_args(cmd)?;
+    let mut args = args.into_iter().peekable();
+    if args.peek().map(|a| a == "curl").unwrap_or(false) {
+        args.next();
+    }
+
+    let mut method: Option<String> = None;
+    let mut url = String::new();
+    let mut headers: Vec<PairUi> = Vec::new();
+    let mut data: Vec<DataPart> = Vec::new();
+    let mut form_data: Vec<PairUi> = Vec::new();
+    let mut upload_file: Option<String> = None;
+    let mut data_as_query = false;
+
+    while let Some(arg) = args.next() {
+        // 拆分 --flag=value 和 -Xvalue
+        let (flag, inline_value) = if let Some(long) = arg.strip_prefix("--") {
+            match long.split_once('=') {
+                Some((f, v)) => (format!("--{}", f), Some(v.to_owned())),
+                None => (arg.clone(), None),
+            }
+        } else if arg.starts_with('-') && arg.len() > 2 && arg.is_char_boundary(2) && takes_value(&arg[..2]) {
+            (arg[..2].to_owned(), Some(arg[2..].to_owned()))
+        } else {
+            (arg.clone(), None)
+        };
+
+        if !flag.starts_with('-') || flag == "-" {
+            // 有多个 URL 时只取第一个; 未识别参数的值不会覆盖已有的完整 URL
+            if url.is_empty() || (!url.contains("://") && arg.contains("://")) {
+                url = arg;
+            }
+            continue;
+        }
+
+        let mut value = || -> Result<String> {
+            match inline_value.clone().or_else(|| args.next()) {
+                Some(v) => Ok(v),
+                None => bail!("参数 {} 缺少值", flag),
+            }
+        };
+
+        match flag.as_str() {
+            "-X" | "--request" => method = Some(value()?.to_uppercase()),
+            "--url" => url = value()?,
+            "-H" | "--header" => {
+                let header = value()?;
+                if let Some((k, v)) = header.split_once(':') {
+                    headers.push(PairUi::from_kv(k.trim(), v.trim()));
+                }
+            }
+            "-d" | "--data" | "--data-raw" | "--data-ascii" | "--data-binary" => data.push(DataPart {
+                value: value()?,
+                raw: false,
+            }),
+            "--data-urlencode" => data.push(DataPart {
+                value: value()?,
+                raw: true,
+            }),
+            "--json" => {
+                headers.push(PairUi::from_kv("Content-Type", "application/json"));
+                data.push(DataPart {
+                    value: value()?,
+                    raw: false,
+                });
+            }
+            "-F" | "--form" | "--form-string" => {
+                let field = value()?;
+                if let Some((k, v)) = field.split_once('=') {
+                    // 去掉 ;type= ;filename= 等附加属性
+                    let v = if v.starts_with('@') {
+                        v.split(';').next().unwrap_or(v)
+                    } else {
+                        v
+                    };
+                    form_data.push(PairUi::from_kv(k, v));
+                }
+            }
+            "-u" | "--user" => {
+                let credentials = value()?;
+                let encoded = general_purpose::STANDARD.encode(credentials.as_bytes());
+                headers.push(PairUi::from_kv("Authorization", &format!("Basic {}", encoded)));
+            }
+            "-A" | "--user-agent" => headers.push(PairUi::from_kv("User-Agent", &value()?)),
+            "-b" | "--cookie" => headers.push(PairUi::from_kv("Cookie", &value()?)),
+            "-e" | "--referer" => headers.push(PairUi::from_kv("Referer", &value()?)),
+            "-T" | "--upload-file" => upload_file = Some(value()?),
+            "-G" | "--get" => data_as_query = true,
+            "-I" | "--head" => method = Some("HEAD".to_owned()),
+            f if takes_value(f) => {
+                value()?;
+            }
+            // --compressed, -k, -L, -s, -v, -i 等
+            _ => {}
+        }
+    }
+
+    if url.is_empty() {
+        bail!("curl 命令中没有 URL");
+    }
+
+    let mut cfg = HttpRequestConfig::default();
+
+    // 查询参数
+    let (base_url, query) = split_query(&url);
+    cfg.url = base_url;
+    cfg.query = query;
+    if data_as_query {
+        for part in data.drain(..) {
+            cfg.query.extend(data_pairs(&part));
+        }
+    }
+
+    let has_body = !data.is_empty() || !form_data.is_empty() || upload_file.is_some();
+    let method = method.unwrap_or_else(|| {
+        if upload_file.is_some() {
+            "PUT".to_owned()
+        } else if has_body {
+            "POST".to_owned()
+        } else {
+            "GET".to_owned()
+        }
+    });
+    cfg.method = Method::from_name(&method).unwrap_or_default();
+
+    let content_type = headers
+        .iter()
+        .find(|h| h.key.eq_ignore_ascii_case("content-type"))
+        .map(|h| h.value.to_lowercase())
+        .unwrap_or_default();
+
+    if !form_data.is_empty() {
+        // multipart 的 boundary 由客户端生成
+        headers.retain(|h| !h.key.eq_ignore_ascii_case("content-type"));
+        cfg.body_tab_ui = RequestBodyTab::FormData;
+        cfg.body_form_data = form_data;
+    } else if let Some(path) = upload_file {
+        cfg.body_raw_type = RequestBodyRawType::BinaryFile;
+        cfg.body_raw = path;
+    } else if let [part] = data.as_slice() {
+        match part.value.strip_prefix('@') {
+            Some(path) if !part.raw => {
+                cfg.body_raw_type = RequestBodyRawType::BinaryFile;
+                cfg.body_raw = path.to_owned();
+            }
+            _ => set_data_body(&mut cfg, &data, &content_type),
+        }
+    } else if !data.is_empty() {
+        set_data_body(&mut cfg, &data, &content_type);
+    }
+
+    if cfg.body_tab_ui == RequestBodyTab::Form {
+        // urlencoded 的 Content-Type 由表单 body 设置
+        headers.retain(|h| !h.key.eq_ignore_ascii_case("content-type"));
+    }
+
+    cfg.header = headers;
+    Ok(cfg)
+}
+
+/// 根据 Content-Type 和内容选择 body 类型
+fn set_data_body(cfg: &mut HttpRequestConfig, data: &[DataPart], content_type: &str) {
+    let body = data
+        .iter()
+        .map(|p| p.value.as_str())
+        .collect::<Vec<_>>()
+        .join("&");
+    let trimmed = body.trim_start();
+
+    let raw_type = if content_type.contains("json") {
+        Some(RequestBodyRawType::Json)
+    } else if content_type.contains("xml") {
+        Some(RequestBodyRawType::XML)
+    } else if !content_type.is_empty() && !content_type.contains("x-www-form-urlencoded") {
+        // text/* 及其他类型按文本发送
+        Some(RequestBodyRawType::Text)
+    } else if serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+        && (trimmed.starts_with('{') || trimmed.starts_with('['))
+    {
+        Some(RequestBodyRawType::Json)
+    } else if trimmed.starts_with('<') {
+        Some(RequestBodyRawType::XML)
+    } else {
+        None
+    };
+
+    match raw_type {
+        Some(raw_type) => {
+            cfg.body_tab_ui = RequestBodyTab::Raw;
+            cfg.body_raw_type = raw_type;
+            cfg.body_raw = body;
+        }
+        None if data.iter().all(|p| p.raw || p.value.contains('=')) => {
+            cfg.body_tab_ui = RequestBodyTab::Form;
+            cfg.body_form = data.iter().flat_map(data_pairs).collect();
+        }
+        None => {
+            cfg.body_tab_ui = RequestBodyTab::Raw;
+            cfg.body_raw_type = RequestBodyRawType::Form;
+            cfg.body_raw = body;
+        }
+    }
+}
+
+fn data_pairs(part: &DataPart) -> Vec<PairUi> {
+    if part.raw {
+        // --data-urlencode name=content
+        return match part.value.split_once('=') {
+            Some((k, v)) => vec![PairUi::from_kv(k, v)],
+            None => vec![PairUi::from_kv(&part.value, "")],
+        };
+    }
+    part.value
+        .split('&')
+        .filter(|kv| !kv.is_empty())
+        .map(|kv| match kv.split_once('=') {
+            Some((k, v)) => PairUi::from_kv(&percent_decode(k), &percent_decode(v)),
+            None => PairUi::from_kv(&percent_decode(kv), ""),
+        })
+        .collect()
+}
+
+pub(crate) fn split_query(url: &str) -> (String, Vec<PairUi>) {
+    let url = url.split('#').next().unwrap_or(url);
+    match url.split_once('?') {
+        Some((base, query)) => (
+            base.to_owned(),
+            data_pairs(&DataPart {
+                value: query.to_owned(),
+                raw: false,
+            }),
+        ),
+        None => (url.to_owned(), Vec::new()),
+    }
+}
+
+pub(crate) fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        out.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b'+' => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// 需要值的参数 (不处理的参数也需要跳过其值), 来自 `curl --help all`
+const VALUE_FLAGS: &[&str] = &[
+    "-A", "-C", "-D", "-E", "-F", "-H", "-K", "-P", "-Q", "-T", "-U", "-X", "-Y", "-b", "-c", "-d",
+    "-e", "-m", "-o", "-r", "-t", "-u", "-w", "-x", "-y", "-z", "--abstract-unix-socket",
+    "--alt-svc", "--aws-sigv4", "--cacert", "--capath", "--cert", "--cert-type", "--ciphers",
+    "--config", "--connect-timeout", "--connect-to", "--continue-at", "--cookie", "--cookie-jar",
+    "--create-file-mode", "--crlfile", "--curves", "--data", "--data-ascii", "--data-binary",
+    "--data-raw", "--data-urlencode", "--delegation", "--dns-interface", "--dns-ipv4-addr",
+    "--dns-ipv6-addr", "--dns-servers", "--doh-url", "--dump-header", "--ech", "--egd-file",
+    "--engine", "--etag-compare", "--etag-save", "--expect100-timeout", "--form", "--form-string",
+    "--ftp-account", "--ftp-alternative-to-user", "--ftp-method", "--ftp-port",
+    "--ftp-ssl-ccc-mode", "--happy-eyeballs-timeout-ms", "--header", "--hostpubmd5",
+    "--hostpubsha256", "--hsts", "--interface", "--ip-tos", "--json", "--keepalive-cnt",
+    "--keepalive-time", "--key", "--key-type", "--krb", "--libcurl", "--limit-rate", "--local-port",
+    "--login-options", "--mail-auth", "--mail-from", "--mail-rcpt", "--max-filesize",
+    "--max-redirs", "--max-time", "--netrc-file", "--noproxy", "--oauth2-bearer", "--output",
+    "--output-dir", "--parallel-max", "--pass", "--pinnedpubkey", "--preproxy", "--proto",
+    "--proto-default", "--proto-redir", "--proxy", "--proxy-cacert", "--proxy-capath",
+    "--proxy-cert", "--proxy-cert-type", "--proxy-ciphers", "--proxy-crlfile", "--proxy-header",
+    "--proxy-key", "--proxy-key-type", "--proxy-pass", "--proxy-pinnedpubkey",
+    "--proxy-service-name", "--proxy-tls13-ciphers", "--proxy-tlsauthtype", "--proxy-tlspassword",
+    "--proxy-tlsuser", "--proxy-user", "--proxy1.0", "--pubkey", "--quote", "--random-file",
+    "--range", "--rate", "--referer", "--request", "--request-target", "--resolve", "--retry",
+    "--retry-delay", "--retry-max-time", "--sasl-authzid", "--service-name", "--socks4",
+    "--socks4a", "--socks5", "--socks5-gssapi-service", "--socks5-hostname", "--speed-limit",
+    "--speed-time", "--stderr", "--telnet-option", "--tftp-blksize", "--time-cond", "--tls-max",
+    "--tls13-ciphers", "--tlsauthtype", "--tlspassword", "--tlsuser", "--trace", "--trace-ascii",
+    "--trace-config", "--unix-socket", "--upload-file", "--url", "--url-query", "--user",
+    "--user-agent", "--variable", "--vlan-priority", "--write-out",
+];
+
+/// 参数是否需要值, `--expand-xxx` 与 `--xxx` 相同
+fn takes_value(flag: &str) -> bool {
+    match flag.strip_prefix("--expand-") {
+        Some(flag) => VALUE_FLAGS.contains(&format!("--{}", flag).as_str()),
+        None => VALUE_FLAGS.contains(&flag),
+    }
+}
+
+/// 按 shell 规则拆分参数, 支持单/双引号, $'...', 反斜杠以及 `\` / `^` 续行
+fn split_args(cmd: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = cmd.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' | '^' if matches!(chars.peek(), Some('\n') | Some('\r')) => {
+                // 续行
+                while matches!(chars.peek(), Some('\n') | Some('\r')) {
+                    chars.next();
+                }
+            }
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                    in_arg = true;
+                }
+            }
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(ch) => current.push(ch),
+                        None => bail!("单引号未闭合"),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => current.push('\n'),
+                            Some('t') => current.push('\t'),
+                            Some('r') => current.push('\r'),
+                            Some(ch) => current.push(ch),
+                            None => bail!("引号未闭合"),
+                        },
+                        Some(ch) => current.push(ch),
+                        None => bail!("引号未闭合"),
+                    }
+                }
+            }
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(ch @ ('"' | '\\' | '$' | '`')) => current.push(ch),
+                            Some('\n') => {}
+                            Some(ch) => {
+                                current.push('\\');
+                                current.push(ch);
+                            }
+                            None => bail!("双引号未闭合"),
+                        },
+                        Some(ch) => current.push(ch),
+                        None => bail!("双引号未闭合"),
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(pairs: &[PairUi]) -> Vec<(&str, &str)> {
+        pairs.iter().map(|p| (p.key.as_str(), p.value.as_str())).collect()
+    }
+
+    #[test]
+    fn split_args_quoting() {
+        let args = split_args(r#"curl -H 'X-A: 1 2' -d "a \"b\" \$c" $'x\ny' plain\ arg"#).unwrap();
+        assert_eq!(args, ["curl", "-H", "X-A: 1 2", "-d", "a \"b\" $c", "x\ny", "plain arg"]);
+    }
+
+    #[test]
+    fn split_args_continuations() {
+        let bash = split_args("curl \\\n  -X POST \\\r\n  https://api/x").unwrap();
+        assert_eq!(bash, ["curl", "-X", "POST", "https://api/x"]);
+        let cmd = split_args("curl ^\r\n  -H \"A: b\" ^\n  https://api/x").unwrap();
+        assert_eq!(cmd, ["curl", "-H", "A: b", "https://api/x"]);
+    }
+
+    #[test]
+    fn split_args_unclosed_quote() {
+        assert!(split_args("curl 'https://api/x").is_err());
+        assert!(split_args("curl \"https://api/x").is_err());
+    }
+
+    #[test]
+    fn parse_method_headers_and_query() {
+        let cfg = parse_curl("curl -XPUT 'https://api/x?a=1&b=%20' -H 'Accept: text/plain' --user-agent=ua").unwrap();
+        assert_eq!(cfg.method, Method::PUT);
+        assert_eq!(cfg.url, "https://api/x");
+        assert_eq!(pairs(&cfg.query), [("a", "1"), ("b", " ")]);
+        assert_eq!(pairs(&cfg.header), [("Accept", "text/plain"), ("User-Agent", "ua")]);
+    }
+
+    #[test]
+    fn parse_skips_values_of_unhandled_flags() {
+        for cmd in [
+            "curl https://api/x --max-redirs 5",
+            "curl --limit-rate 1M https://api/x",
+            "curl https://api/x --oauth2-bearer token --retry-max-time 10",
+            "curl --interface eth0 -K conf.txt -U user:pass https://api/x",
+            "curl https://api/x --expand-data '{{a}}'",
+        ] {
+            let cfg = parse_curl(cmd).unwrap();
+            assert_eq!(cfg.url, "https://api/x", "{}", cmd);
+        }
+    }
+
+    #[test]
+    fn parse_keeps_first_url() {
+        let cfg = parse_curl("curl https://api/x https://api/y").unwrap();
+        assert_eq!(cfg.url, "https://api/x");
+        // 未识别参数的值在 URL 之前时, 后面的完整 URL 仍然生效
+        let cfg = parse_curl("curl --unknown-flag 5 https://api/x").unwrap();
+        assert_eq!(cfg.url, "https://api/x");
+    }
+
+    #[test]
+    fn parse_data_bodies() {
+        let cfg = parse_curl(r#"curl https://api/x -d '{"a":1}'"#).unwrap();
+        assert_eq!(cfg.method, Method::POST);
+        assert_eq!(cfg.body_tab_ui, RequestBodyTab::Raw);
+        assert_eq!(cfg.body_raw_type, RequestBodyRawType::Json);
+        assert_eq!(cfg.body_raw, r#"{"a":1}"#);
+
+        let cfg = parse_curl("curl https://api/x -d a=1 --data-urlencode 'b=x y'").unwrap();
+        assert_eq!(cfg.body_tab_ui, RequestBodyTab::Form);
+        assert_eq!(pairs(&cfg.body_form), [("a", "1"), ("b", "x y")]);
+
+        let cfg = parse_curl("curl -G https://api/x -d a=1").unwrap();
+        assert_eq!(cfg.method, Method::GET);
+        assert_eq!(pairs(&cfg.query), [("a", "1")]);
+    }
+
+    #[test]
+    fn parse_form_and_auth() {
+        let cfg = parse_curl("curl https://api/x -F 'f=@a.png;type=image/png' -F n=1 -u u:p").unwrap();
+        assert_eq!(cfg.body_tab_ui, RequestBodyTab::FormData);
+        assert_eq!(pairs(&cfg.body_form_data), [("f", "@a.png"), ("n", "1")]);
+        assert_eq!(pairs(&cfg.header), [("Authorization", "Basic dTpw")]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse_curl("curl -H 'A: b'").is_err());
+        assert!(parse_curl("curl https://api/x -H").is_err());
+    }
+}
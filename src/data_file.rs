@@ -0,0 +1,70 @@
+//! 数据驱动运行的数据文件
+//!
+//! - CSV: 第一行为列名
+//! - JSON: 对象数组, 非字符串的值按 JSON 文本使用
+//!
+//! 每一行对应一组变量, 列名为变量名
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+
+use crate::PairUi;
+
+/// 读取数据文件, .json 按 JSON 解析, 其他按 CSV 解析
+///
+/// 相对路径相对于 base_dir (一般为项目文件所在目录), 未保存的项目相对于当前目录
+pub fn load_data_file(path: &str, base_dir: Option<&Path>) -> Result<Vec<Vec<PairUi>>> {
+    let full_path = base_dir.map_or_else(|| Path::new(path).to_path_buf(), |dir| dir.join(path));
+    let data = std::fs::read_to_string(&full_path).with_context(|| format!("读取数据文件 {} 失败", path))?;
+    let is_json = Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let rows = if is_json { parse_json(&data) } else { parse_csv(&data) };
+    rows.with_context(|| format!("解析数据文件 {} 失败", path))
+}
+
+pub fn parse_csv(data: &str) -> Result<Vec<Vec<PairUi>>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::Headers)
+        .flexible(true)
+        .from_reader(data.as_bytes());
+    let headers = reader.headers()?.clone();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        rows.push(
+            headers
+                .iter()
+                .zip(record.iter())
+                .filter(|(key, _)| !key.is_empty())
+                .map(|(key, value)| PairUi::from_kv(key, value))
+                .collect(),
+        );
+    }
+    Ok(rows)
+}
+
+pub fn parse_json(data: &str) -> Result<Vec<Vec<PairUi>>> {
+    let Value::Array(items) = serde_json::from_str(data)? else {
+        bail!("JSON 数据文件应为对象数组");
+    };
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let Some(obj) = item.as_object() else {
+                bail!("第 {} 项不是对象", i + 1);
+            };
+            Ok(obj
+                .iter()
+                .map(|(key, value)| match value {
+                    Value::String(s) => PairUi::from_kv(key, s),
+                    Value::Null => PairUi::from_kv(key, ""),
+                    v => PairUi::from_kv(key, &v.to_string()),
+                })
+                .collect())
+        })
+        .collect()
+}
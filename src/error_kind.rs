@@ -0,0 +1,102 @@
+//! 请求错误分类
+
+use std::io;
+
+/// 请求失败的原因
+#[derive(Debug, strum::AsRefStr, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorKind {
+    #[strum(serialize = "Connection refused")]
+    ConnectRefused,
+    /// 其他连接错误
+    Connect,
+    Timeout,
+    #[strum(serialize = "DNS")]
+    Dns,
+    #[strum(serialize = "TLS")]
+    Tls,
+    #[strum(serialize = "Body read")]
+    BodyRead,
+    /// 前置或后置脚本执行失败
+    Script,
+    Other,
+}
+
+impl ErrorKind {
+    /// 根据错误链判断失败原因
+    pub fn classify(err: &anyhow::Error) -> Self {
+        let message = message(err).to_lowercase();
+        if message.contains("dns error")
+            || message.contains("failed to lookup address")
+            || message.contains("name or service not known")
+        {
+            return Self::Dns;
+        }
+        if ["certificate", "tls", "ssl", "handshake"].iter().any(|k| message.contains(k)) {
+            return Self::Tls;
+        }
+
+        let mut connect = false;
+        for cause in err.chain() {
+            if let Some(e) = cause.downcast_ref::<io::Error>() {
+                match e.kind() {
+                    io::ErrorKind::ConnectionRefused => return Self::ConnectRefused,
+                    io::ErrorKind::TimedOut => return Self::Timeout,
+                    io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted => connect = true,
+                    _ => {}
+                }
+            }
+            if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                if e.is_timeout() {
+                    return Self::Timeout;
+                }
+                if e.is_body() || e.is_decode() {
+                    return Self::BodyRead;
+                }
+                connect |= e.is_connect();
+            }
+        }
+
+        if message.contains("connection refused") {
+            Self::ConnectRefused
+        } else if connect {
+            Self::Connect
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// 错误链合并为一行, 跳过已包含在前面信息中的原因
+pub fn message(err: &anyhow::Error) -> String {
+    let mut message = String::new();
+    for cause in err.chain() {
+        let cause = cause.to_string();
+        if message.contains(&cause) {
+            continue;
+        }
+        if !message.is_empty() {
+            message.push_str(": ");
+        }
+        message.push_str(&cause);
+    }
+    message
+}
+
+/// 同一类错误的次数和示例
+#[derive(Debug, Clone, Default)]
+pub struct ErrorSummary {
+    pub count: usize,
+    /// 不重复的错误信息, 最多 MAX_SAMPLES 条
+    pub samples: Vec<String>,
+}
+
+impl ErrorSummary {
+    const MAX_SAMPLES: usize = 3;
+
+    pub fn add(&mut self, message: String) {
+        self.count += 1;
+        if self.samples.len() < Self::MAX_SAMPLES && !self.samples.contains(&message) {
+            self.samples.push(message);
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! 导入和导出 HAR (HTTP Archive 1.2)
+
+use anyhow::{bail, Result};
+use base64::Engine;
+use serde_json::{json, Value};
+
+use crate::curl::{percent_decode, split_query};
+use crate::snippet::{self, Body, Part};
+use crate::{
+    util, Group, HttpRequestConfig, HttpResponse, HttpTest, ImportResult, Method, PairUi, Project,
+    RequestBodyRawType, RequestBodyTab, APPLICATION_FORM, APPLICATION_STREAM, CONTENT_TYPE,
+};
+
+/// 每个 entry 对应一个 HttpTest, 按 host 分组
+pub fn import_har(data: &str) -> Result<ImportResult> {
+    let json: Value = serde_json::from_str(data)?;
+    let Some(entries) = json["log"]["entries"].as_array() else {
+        bail!("不是 HAR 文件: 缺少 log.entries");
+    };
+
+    let mut result = ImportResult::default();
+    for entry in entries {
+        let request = &entry["request"];
+        let Some(url) = request["url"].as_str() else {
+            continue;
+        };
+        let method = request["method"].as_str().unwrap_or("GET");
+        let Some(method) = Method::from_name(method) else {
+            result.warnings.push(format!("不支持的方法 {} {}", method, url));
+            continue;
+        };
+
+        let (base, mut query) = split_query(url);
+        if let Some(query_string) = request["queryString"].as_array() {
+            query = name_value_pairs(query_string);
+        }
+
+        let mut cfg = HttpRequestConfig {
+            method,
+            url: base,
+            query,
+            header: request["headers"]
+                .as_array()
+                .map(|headers| {
+                    name_value_pairs(headers)
+                        .into_iter()
+                        // HTTP/2 伪头部和由客户端生成的头部
+                        .filter(|h| {
+                            !h.key.starts_with(':')
+                                && !h.key.eq_ignore_ascii_case("content-length")
+                                && !h.key.eq_ignore_ascii_case("host")
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+        set_post_data(&mut cfg, &request["postData"]);
+
+        // 导出时在 comment 中保存了测试名
+        let name = entry["comment"]
+            .as_str()
+            .filter(|c| !c.is_empty())
+            .map(|c| c.to_owned())
+            .unwrap_or_else(|| format!("{} {}", cfg.method.as_ref(), path_of(&cfg.url)));
+        let mut test = HttpTest::from_name(name);
+        test.request = cfg;
+
+        let host = host_of(url);
+        match result.groups.iter_mut().find(|g| g.name == host) {
+            Some(group) => group.childrent.push(test),
+            None => {
+                let mut group = Group::from_name(host.to_owned());
+                group.childrent.push(test);
+                result.groups.push(group);
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+fn set_post_data(cfg: &mut HttpRequestConfig, post_data: &Value) {
+    if post_data.is_null() {
+        return;
+    }
+    let mime = post_data["mimeType"].as_str().unwrap_or_default();
+    let text = post_data["text"].as_str().unwrap_or_default();
+    let params = post_data["params"].as_array();
+
+    if mime.contains("multipart/form-data") && params.is_some() {
+        cfg.body_tab_ui = RequestBodyTab::FormData;
+        cfg.body_form_data = params
+            .into_iter()
+            .flatten()
+            .map(|p| {
+                let name = p["name"].as_str().unwrap_or_default();
+                match p["fileName"].as_str() {
+                    Some(file) => PairUi::from_kv(name, &format!("@{}", file)),
+                    None => PairUi::from_kv(name, p["value"].as_str().unwrap_or_default()),
+                }
+            })
+            .collect();
+    } else if mime.contains(APPLICATION_FORM) {
+        cfg.body_tab_ui = RequestBodyTab::Form;
+        cfg.body_form = match params {
+            Some(params) if !params.is_empty() => name_value_pairs(params)
+                .into_iter()
+                .map(|p| PairUi::from_kv(&percent_decode(&p.key), &percent_decode(&p.value)))
+                .collect(),
+            _ => split_query(&format!("?{}", text)).1,
+        };
+    } else {
+        cfg.body_tab_ui = RequestBodyTab::Raw;
+        cfg.body_raw_type = if mime.contains("json") {
+            RequestBodyRawType::Json
+        } else if mime.contains("xml") {
+            RequestBodyRawType::XML
+        } else {
+            RequestBodyRawType::Text
+        };
+        cfg.body_raw = text.to_owned();
+        return;
+    }
+
+    // 表单的 Content-Type 由客户端生成 (multipart 需要 boundary)
+    cfg.header.retain(|h| !h.key.eq_ignore_ascii_case(CONTENT_TYPE));
+}
+
+fn name_value_pairs(values: &[Value]) -> Vec<PairUi> {
+    values
+        .iter()
+        .filter_map(|v| {
+            let name = v["name"].as_str()?;
+            Some(PairUi::from_kv(name, v["value"].as_str().unwrap_or_default()))
+        })
+        .collect()
+}
+
+fn host_of(url: &str) -> &str {
+    let rest = url.split_once("://").map(|(_, r)| r).unwrap_or(url);
+    rest.split(['/', '?', '#']).next().unwrap_or(rest)
+}
+
+fn path_of(url: &str) -> &str {
+    let rest = url.split_once("://").map(|(_, r)| r).unwrap_or(url);
+    rest.find('/').map(|i| &rest[i..]).unwrap_or("/")
+}
+
+/// 导出项目中已执行的请求及其最近一次响应
+///
+/// 请求使用实际发出的内容 (含前置脚本的修改和动态变量的取值)
+pub fn export_har(project: &Project) -> Result<String> {
+    let vars = &project.resolved_variables();
+    let mut entries = Vec::new();
+    for test in project.groups.iter().flat_map(|g| g.childrent.iter()) {
+        let Some(response) = &test.response else {
+            continue;
+        };
+        if test.request.method == Method::WS {
+            continue;
+        }
+        let wait = response.latency.as_secs_f64() * 1000.0;
+        let receive = response.receive_time.as_secs_f64() * 1000.0;
+        entries.push(json!({
+            "startedDateTime": util::iso_timestamp(response.started_at),
+            "time": wait + receive,
+            "comment": test.name,
+            "request": har_request(&test.request, vars, response)?,
+            "response": har_response(response),
+            "cache": {},
+            // 无法从客户端区分发送请求和等待响应的时间, 都计入 wait
+            "timings": {
+                "send": 0,
+                "wait": wait,
+                "receive": receive,
+            },
+        }));
+    }
+
+    if entries.is_empty() {
+        bail!("没有已执行的请求可导出");
+    }
+
+    Ok(serde_json::to_string_pretty(&json!({
+        "log": {
+            "version": "1.2",
+            "creator": {
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "entries": entries,
+        }
+    }))?)
+}
+
+fn har_request(cfg: &HttpRequestConfig, vars: &Vec<PairUi>, response: &HttpResponse) -> Result<Value> {
+    let sent = &response.request;
+    let content_type = sent.header(CONTENT_TYPE).unwrap_or_default();
+
+    let post_data = match &sent.body {
+        Some(body) if content_type.starts_with(APPLICATION_FORM) => {
+            let text = String::from_utf8_lossy(body);
+            json!({
+                "mimeType": content_type,
+                "params": split_query(&format!("?{}", text))
+                    .1
+                    .iter()
+                    .map(|p| json!({ "name": percent_decode(&p.key), "value": percent_decode(&p.value) }))
+                    .collect::<Vec<_>>(),
+                "text": text,
+            })
+        }
+        Some(body) => match std::str::from_utf8(body) {
+            Ok(text) => json!({ "mimeType": content_type, "text": text }),
+            Err(_) => json!({
+                "mimeType": content_type,
+                "text": base64::engine::general_purpose::STANDARD.encode(body),
+                "encoding": "base64",
+            }),
+        },
+        // multipart 和文件请求体以流的形式发送, 按配置描述其中的字段和文件
+        None => match snippet::resolve(cfg, vars)?.body {
+            Body::Multipart(parts) => json!({
+                "mimeType": content_type,
+                "params": parts
+                    .iter()
+                    .map(|p| match p {
+                        Part::Text(k, v) => json!({ "name": k, "value": v }),
+                        Part::File(k, path) => json!({ "name": k, "fileName": path }),
+                    })
+                    .collect::<Vec<_>>(),
+            }),
+            Body::File(path) => json!({
+                "mimeType": if content_type.is_empty() { APPLICATION_STREAM } else { content_type },
+                "text": "",
+                "comment": format!("file: {}", path),
+            }),
+            _ => Value::Null,
+        },
+    };
+
+    let query: Vec<Value> = reqwest::Url::parse(&sent.url)
+        .map(|url| {
+            url.query_pairs()
+                .map(|(k, v)| json!({ "name": k, "value": v }))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut value = json!({
+        "method": sent.method,
+        "url": sent.url,
+        "httpVersion": format!("{:?}", response.version),
+        "headers": sent
+            .headers
+            .iter()
+            .map(|(k, v)| json!({ "name": k, "value": v }))
+            .collect::<Vec<_>>(),
+        "queryString": query,
+        "cookies": [],
+        "headersSize": -1,
+        "bodySize": sent.body.as_ref().map_or(response.request_size, |b| b.len() as u64),
+    });
+    if !post_data.is_null() {
+        value["postData"] = post_data;
+    }
+    Ok(value)
+}
+
+fn har_response(response: &HttpResponse) -> Value {
+    let data = response.data_vec.as_deref().unwrap_or_default();
+    let mut content = json!({
+        "size": data.len(),
+        "mimeType": response.content_type().unwrap_or_default(),
+    });
+    match std::str::from_utf8(data) {
+        Ok(text) => content["text"] = json!(text),
+        Err(_) => {
+            content["text"] = json!(base64::engine::general_purpose::STANDARD.encode(data));
+            content["encoding"] = json!("base64");
+        }
+    }
+
+    json!({
+        "status": response.status.as_u16(),
+        "statusText": response.status.canonical_reason().unwrap_or_default(),
+        "httpVersion": format!("{:?}", response.version),
+        "headers": response
+            .headers
+            .iter()
+            .map(|(k, v)| json!({ "name": k.as_str(), "value": v.to_str().unwrap_or_default() }))
+            .collect::<Vec<_>>(),
+        "cookies": [],
+        "content": content,
+        "redirectURL": "",
+        "headersSize": -1,
+        "bodySize": response.response_size,
+    })
+}
@@ -0,0 +1,85 @@
+//! 响应时间直方图
+//!
+//! 基于 HDR 直方图, 以微秒记录, 内存固定, 百分位数误差不超过 0.1%, 与请求数无关
+
+use std::time::Duration;
+
+use hdrhistogram::Histogram;
+
+/// 可记录的最大响应时间 (1 小时), 超过时按最大值记录
+const MAX_LATENCY_US: u64 = 3_600_000_000;
+/// 有效数字位数
+const SIGNIFICANT_FIGURES: u8 = 3;
+
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHistogram {
+    /// 第一次记录时才分配
+    histogram: Option<Histogram<u64>>,
+    count: u64,
+    sum_us: u128,
+    min_us: u64,
+    max_us: u64,
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, latency: Duration) {
+        let us = (latency.as_micros() as u64).min(MAX_LATENCY_US);
+        self.histogram
+            .get_or_insert_with(|| {
+                Histogram::new_with_max(MAX_LATENCY_US, SIGNIFICANT_FIGURES).expect("valid histogram bounds")
+            })
+            .saturating_record(us);
+        if self.count == 0 || us < self.min_us {
+            self.min_us = us;
+        }
+        self.max_us = self.max_us.max(us);
+        self.count += 1;
+        self.sum_us += us as u128;
+    }
+
+    /// 清空记录, 保留已分配的内存
+    pub fn reset(&mut self) {
+        if let Some(histogram) = &mut self.histogram {
+            histogram.reset();
+        }
+        self.count = 0;
+        self.sum_us = 0;
+        self.min_us = 0;
+        self.max_us = 0;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// 最小值 (毫秒)
+    pub fn min_ms(&self) -> Option<f64> {
+        (!self.is_empty()).then(|| us_to_ms(self.min_us))
+    }
+
+    /// 最大值 (毫秒)
+    pub fn max_ms(&self) -> Option<f64> {
+        (!self.is_empty()).then(|| us_to_ms(self.max_us))
+    }
+
+    /// 平均值 (毫秒)
+    pub fn mean_ms(&self) -> Option<f64> {
+        (!self.is_empty()).then(|| self.sum_us as f64 / self.count as f64 / 1000.0)
+    }
+
+    /// 百分位数 (毫秒), p 为 0 ~ 100
+    pub fn percentile_ms(&self, p: f64) -> Option<f64> {
+        let histogram = self.histogram.as_ref().filter(|h| !h.is_empty())?;
+        let us = histogram.value_at_quantile((p / 100.0).clamp(0.0, 1.0));
+        // 桶的上界可能超过实际最大值
+        Some(us_to_ms(us.clamp(self.min_us, self.max_us)))
+    }
+}
+
+fn us_to_ms(us: u64) -> f64 {
+    us as f64 / 1000.0
+}
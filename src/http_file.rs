@@ -0,0 +1,354 @@
+//! 读写 .http / .rest 请求文件 (VS Code REST Client / JetBrains HTTP Client 格式)
+//!
+//! ```text
+//! @base = https://api.example.com
+//!
+//! ### 获取用户
+//! GET {{base}}/users?page=1
+//! Accept: application/json
+//!
+//! ### 创建用户
+//! POST {{base}}/users
+//! Content-Type: application/json
+//!
+//! < ./user.json
+//! ```
+
+use std::path::Path;
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::snippet::percent_encode;
+use crate::{
+    util, Environment, Group, HttpRequestConfig, HttpTest, ImportResult, Method, PairUi, RequestBodyRawType,
+    RequestBodyTab, APPLICATION_FORM, APPLICATION_JSON, CONTENT_TYPE, TEXT_PLAIN, TEXT_XML,
+};
+
+lazy_static! {
+    static ref VAR_DEFINE: Regex = Regex::new(r"^@([\w.-]+)\s*=\s*(.*)$").unwrap();
+    static ref NAME_COMMENT: Regex = Regex::new(r"^(?:#|//)\s*@name\s+(.+)$").unwrap();
+    static ref VAR_REF: Regex = Regex::new(r"\{\{[^}]*\}\}").unwrap();
+    static ref VAR_NAME: Regex = Regex::new(r"\{\{\s*([\w.-]+)\s*\}\}").unwrap();
+}
+
+/// 解析 .http 文件为一个 Group, `@var = value` 定义保存到与文件同名的环境中
+///
+/// `< ./file` 引用的文件路径相对于 base_dir
+pub fn parse_http_file(name: &str, data: &str, base_dir: Option<&Path>) -> Result<ImportResult> {
+    let mut result = ImportResult::default();
+    let mut group = Group::from_name(name.to_owned());
+
+    let mut block: Vec<&str> = Vec::new();
+    let mut block_name = String::new();
+    for line in data.lines() {
+        if let Some(title) = line.strip_prefix("###") {
+            parse_block(&block, &block_name, base_dir, &mut group, &mut result);
+            block.clear();
+            block_name = title.trim().to_owned();
+        } else {
+            block.push(line);
+        }
+    }
+    parse_block(&block, &block_name, base_dir, &mut group, &mut result);
+
+    if !group.childrent.is_empty() {
+        result.groups.push(group);
+    }
+    if !result.variables.is_empty() {
+        result.environments.push(Environment {
+            name: name.to_owned(),
+            variables: std::mem::take(&mut result.variables),
+        });
+    }
+    Ok(result)
+}
+
+fn parse_block(
+    lines: &[&str],
+    title: &str,
+    base_dir: Option<&Path>,
+    group: &mut Group,
+    result: &mut ImportResult,
+) {
+    let mut name = title.to_owned();
+    let mut iter = lines.iter().map(|l| l.trim_end()).peekable();
+
+    // 请求行之前: 变量定义, 注释和空行
+    let mut request_line = None;
+    for line in iter.by_ref() {
+        let trimmed = line.trim();
+        if let Some(caps) = VAR_DEFINE.captures(trimmed) {
+            let var = PairUi::from_kv(&caps[1], caps[2].trim());
+            match result.variables.iter_mut().find(|v| v.key == var.key) {
+                Some(existing) => existing.value = var.value,
+                None => result.variables.push(var),
+            }
+        } else if let Some(caps) = NAME_COMMENT.captures(trimmed) {
+            name = caps[1].trim().to_owned();
+        } else if !trimmed.is_empty() && !trimmed.starts_with('#') && !trimmed.starts_with("//") {
+            request_line = Some(trimmed);
+            break;
+        }
+    }
+    let Some(request_line) = request_line else {
+        return;
+    };
+
+    let mut parts = request_line.split_whitespace();
+    let first = parts.next().unwrap_or_default();
+    let (method, mut url) = match Method::from_name(first) {
+        Some(method) => (method, parts.next().unwrap_or_default().to_owned()),
+        None => (Method::GET, first.to_owned()),
+    };
+
+    // 多行查询参数: 以 ? 或 & 开头的续行
+    while let Some(line) = iter.next_if(|l| l.trim_start().starts_with(['?', '&'])) {
+        url.push_str(line.trim());
+    }
+
+    let mut cfg = HttpRequestConfig {
+        method,
+        ..Default::default()
+    };
+    let (base, query) = split_url(&url);
+    cfg.url = base;
+    cfg.query = query;
+
+    // 请求头直到空行
+    for line in iter.by_ref() {
+        if line.trim().is_empty() {
+            break;
+        }
+        if line.trim_start().starts_with('#') || line.trim_start().starts_with("//") {
+            continue;
+        }
+        if let Some((k, v)) = line.split_once(':') {
+            cfg.header.push(PairUi::from_kv(k.trim(), v.trim()));
+        }
+    }
+
+    let body = iter.collect::<Vec<_>>().join("\n");
+    let body = body.trim_end();
+    if !body.is_empty() {
+        set_body(&mut cfg, body, base_dir);
+    }
+
+    if name.is_empty() {
+        name = format!("{} {}", cfg.method.as_ref(), cfg.url);
+    }
+    let multipart = cfg
+        .header
+        .iter()
+        .any(|h| h.key.eq_ignore_ascii_case(CONTENT_TYPE) && h.value.contains("multipart/"));
+    if multipart {
+        result
+            .warnings
+            .push(format!("{}: multipart 请求体按原文导入, 其中的文件引用不会被读取", name));
+    }
+    let mut test = HttpTest::from_name(name);
+    test.request = cfg;
+    group.childrent.push(test);
+}
+
+fn set_body(cfg: &mut HttpRequestConfig, body: &str, base_dir: Option<&Path>) {
+    cfg.body_tab_ui = RequestBodyTab::Raw;
+
+    // `< ./file` 或 `<@ ./file`
+    let include = body
+        .strip_prefix("<@")
+        .or_else(|| body.strip_prefix('<'))
+        .filter(|path| !body.contains('\n') && !path.trim().is_empty());
+    if let Some(path) = include {
+        let path = path.trim();
+        cfg.body_raw_type = RequestBodyRawType::BinaryFile;
+        cfg.body_raw = match base_dir {
+            Some(dir) if Path::new(path).is_relative() => dir.join(path).to_string_lossy().into_owned(),
+            _ => path.to_owned(),
+        };
+        return;
+    }
+
+    let content_type = cfg
+        .header
+        .iter()
+        .find(|h| h.key.eq_ignore_ascii_case(CONTENT_TYPE))
+        .map(|h| h.value.to_lowercase())
+        .unwrap_or_default();
+    cfg.body_raw_type = if content_type.contains("json") {
+        RequestBodyRawType::Json
+    } else if content_type.contains("xml") {
+        RequestBodyRawType::XML
+    } else if content_type.contains(APPLICATION_FORM) {
+        RequestBodyRawType::Form
+    } else {
+        RequestBodyRawType::Text
+    };
+    cfg.body_raw = body.to_owned();
+}
+
+/// 拆分查询参数, 保留 `{{var}}`
+fn split_url(url: &str) -> (String, Vec<PairUi>) {
+    match url.split_once('?') {
+        Some((base, query)) => (
+            base.to_owned(),
+            query
+                .split('&')
+                .filter(|kv| !kv.is_empty())
+                .map(|kv| {
+                    let (k, v) = kv.split_once('=').unwrap_or((kv, ""));
+                    PairUi::from_kv(&crate::curl::percent_decode(k), &crate::curl::percent_decode(v))
+                })
+                .collect(),
+        ),
+        None => (url.to_owned(), Vec::new()),
+    }
+}
+
+/// 将一个 Group 写为 .http 文件内容, vars 中被请求引用到的变量写为文件开头的 `@var = value`
+///
+/// 脚本和断言无法用该格式表示, 不会写出; WS 请求会被跳过
+pub fn write_http_file(group: &Group, vars: &[PairUi]) -> String {
+    let requests = write_requests(group);
+
+    // 只写出请求 (以及被引用变量的值) 中用到的变量, 避免把其他环境值带出去
+    let mut used: Vec<&str> = VAR_NAME.captures_iter(&requests).map(|c| c.get(1).unwrap().as_str()).collect();
+    let vars: Vec<&PairUi> = vars.iter().filter(|v| !v.bad()).collect();
+    let mut i = 0;
+    while i < used.len() {
+        if let Some(var) = vars.iter().find(|v| v.key == used[i]) {
+            for caps in VAR_NAME.captures_iter(&var.value) {
+                let name = caps.get(1).unwrap().as_str();
+                if !used.contains(&name) {
+                    used.push(name);
+                }
+            }
+        }
+        i += 1;
+    }
+
+    let mut out = String::new();
+    for var in vars.iter().filter(|v| used.contains(&v.key.as_str())) {
+        out.push_str(&format!("@{} = {}\n", var.key, var.value));
+    }
+    if !out.is_empty() && !requests.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(&requests);
+    out
+}
+
+fn write_requests(group: &Group) -> String {
+    let mut out = String::new();
+    for test in group.childrent.iter().filter(|t| t.request.method != Method::WS) {
+        let cfg = &test.request;
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("### {}\n", test.name));
+
+        let mut url = cfg.url.to_owned();
+        let query = util::tuple_vec(&cfg.query);
+        if !query.is_empty() {
+            url.push(if url.contains('?') { '&' } else { '?' });
+            url.push_str(
+                &query
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", encode_keep_vars(k), encode_keep_vars(v)))
+                    .collect::<Vec<_>>()
+                    .join("&"),
+            );
+        }
+        out.push_str(&format!("{} {}\n", cfg.method.as_ref(), url));
+
+        let mut headers: Vec<(String, String)> = util::tuple_vec(&cfg.header)
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            .collect();
+        let body = body_text(cfg, &mut headers);
+        for (k, v) in &headers {
+            out.push_str(&format!("{}: {}\n", k, v));
+        }
+        if let Some(body) = body {
+            out.push('\n');
+            out.push_str(&body);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// 请求体文本, 需要时补充 Content-Type
+fn body_text(cfg: &HttpRequestConfig, headers: &mut Vec<(String, String)>) -> Option<String> {
+    let mut set_content_type = |content_type: &str| {
+        if !headers.iter().any(|(k, _)| k.eq_ignore_ascii_case(CONTENT_TYPE)) {
+            headers.push((CONTENT_TYPE.to_owned(), content_type.to_owned()));
+        }
+    };
+
+    match cfg.body_tab_ui {
+        RequestBodyTab::Raw if cfg.body_raw.is_empty() => None,
+        RequestBodyTab::Raw => Some(match cfg.body_raw_type {
+            RequestBodyRawType::BinaryFile => format!("< {}", cfg.body_raw),
+            ref raw_type => {
+                set_content_type(match raw_type {
+                    RequestBodyRawType::Json => APPLICATION_JSON,
+                    RequestBodyRawType::Form => APPLICATION_FORM,
+                    RequestBodyRawType::XML => TEXT_XML,
+                    _ => TEXT_PLAIN,
+                });
+                cfg.body_raw.to_owned()
+            }
+        }),
+        RequestBodyTab::Form => {
+            set_content_type(APPLICATION_FORM);
+            Some(
+                util::tuple_vec(&cfg.body_form)
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", encode_keep_vars(k), encode_keep_vars(v)))
+                    .collect::<Vec<_>>()
+                    .join("&"),
+            )
+        }
+        RequestBodyTab::FormData => {
+            const BOUNDARY: &str = "----ApiTestFormBoundary";
+            set_content_type(&format!("multipart/form-data; boundary={}", BOUNDARY));
+            let mut body = String::new();
+            for (k, v) in util::tuple_vec(&cfg.body_form_data) {
+                if !v.is_empty() && v.contains('@') {
+                    for path in v.split('@').map(|e| e.trim()).filter(|e| !e.is_empty()) {
+                        let file_name = Path::new(path)
+                            .file_name()
+                            .map(|n| n.to_string_lossy().into_owned())
+                            .unwrap_or_else(|| path.to_owned());
+                        body.push_str(&format!(
+                            "--{}\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\n\n< {}\n",
+                            BOUNDARY, k, file_name, path
+                        ));
+                    }
+                } else {
+                    body.push_str(&format!(
+                        "--{}\nContent-Disposition: form-data; name=\"{}\"\n\n{}\n",
+                        BOUNDARY, k, v
+                    ));
+                }
+            }
+            body.push_str(&format!("--{}--", BOUNDARY));
+            Some(body)
+        }
+    }
+}
+
+/// 编码查询参数, `{{var}}` 保持原样
+fn encode_keep_vars(s: &str) -> String {
+    let mut out = String::new();
+    let mut last = 0;
+    for m in VAR_REF.find_iter(s) {
+        out.push_str(&percent_encode(&s[last..m.start()]));
+        out.push_str(m.as_str());
+        last = m.end();
+    }
+    out.push_str(&percent_encode(&s[last..]));
+    out
+}
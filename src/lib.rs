@@ -2,19 +2,38 @@ use anyhow::{bail, Result};
 use reqwest::{header::HeaderMap, RequestBuilder};
 use serde::{Deserialize, Serialize};
 use std::sync::OnceLock;
-mod util;
+pub mod util;
 pub mod script_engine;
+pub mod runner;
+pub mod assertion;
+pub mod curl;
+pub mod snippet;
+pub mod postman;
+pub mod openapi;
+pub mod har;
+pub mod http_file;
+pub mod load;
+pub mod histogram;
+pub mod timeline;
+pub mod error_kind;
+pub mod report;
+pub mod data_file;
 
 static HTTP_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
 
-fn get_http_client() -> &'static reqwest::Client {
+/// 共享客户端的设置
+pub(crate) fn http_client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .pool_max_idle_per_host(10000)
+        .pool_idle_timeout(std::time::Duration::from_secs(60))
+        .tcp_keepalive(std::time::Duration::from_secs(60))
+        .timeout(std::time::Duration::from_secs(30))
+        .connect_timeout(std::time::Duration::from_secs(10))
+}
+
+pub(crate) fn get_http_client() -> &'static reqwest::Client {
     HTTP_CLIENT.get_or_init(|| {
-        reqwest::Client::builder()
-            .pool_max_idle_per_host(10000)
-            .pool_idle_timeout(std::time::Duration::from_secs(60))
-            .tcp_keepalive(std::time::Duration::from_secs(60))
-            .timeout(std::time::Duration::from_secs(30))
-            .connect_timeout(std::time::Duration::from_secs(10))
+        http_client_builder()
             .build()
             .expect("Failed to create HTTP client")
     })
@@ -41,15 +60,141 @@ pub struct RequestStats {
     pub sending: usize,
     pub success: usize,
     pub failed: usize,
-    pub response_times: Vec<u128>,
+    /// 断言未通过的响应数
+    pub assertion_failed: usize,
+    /// 响应时间分布
+    pub latency: histogram::LatencyHistogram,
+    /// 每秒统计
+    pub timeline: timeline::Timeline,
+    /// 各状态码的响应数
+    pub status_codes: std::collections::BTreeMap<u16, usize>,
+    /// 各类错误的次数和示例, 包括脚本错误
+    pub errors: std::collections::BTreeMap<error_kind::ErrorKind, error_kind::ErrorSummary>,
     pub total_start_time: Option<std::time::Instant>,
     pub total_end_time: Option<std::time::Instant>,
     pub total_upload_bytes: u64,
     pub total_download_bytes: u64,
-    pub max_response_times: usize,
+    /// 批量发送是否在进行中
+    pub running: bool,
+    /// 计划发送的请求数, 无法预先确定时为 None
+    pub planned_requests: Option<usize>,
+    /// 计划持续时长 (秒)
+    pub planned_duration: Option<f64>,
+    /// 多阶段负载中每个阶段的统计, 开始/结束时间为阶段的计划时间
+    pub stage_stats: Vec<RequestStats>,
+    /// 执行了脚本的响应数
+    pub script_runs: usize,
+    /// 请求前/响应后脚本的总耗时
+    pub script_time: std::time::Duration,
 }
 
 impl RequestStats {
+    /// 记录一个响应, completed_at 为请求完成的时间, 用于按秒归入时间线
+    pub fn record_response(&mut self, response: &HttpResponse, completed_at: std::time::Instant) {
+        self.latency.record(response.latency);
+        self.total_upload_bytes += response.request_size;
+        self.total_download_bytes += response.response_size;
+        if !response.assertions_passed() {
+            self.assertion_failed += 1;
+        }
+        if response.status.is_success() {
+            self.success += 1;
+        } else {
+            self.failed += 1;
+        }
+        *self.status_codes.entry(response.status.as_u16()).or_default() += 1;
+        if response.pre_script_time.is_some() || response.post_script_time.is_some() {
+            self.script_runs += 1;
+            self.script_time += response.pre_script_time.unwrap_or_default() + response.post_script_time.unwrap_or_default();
+        }
+        for err in &response.script_errors {
+            self.errors.entry(error_kind::ErrorKind::Script).or_default().add(err.to_owned());
+        }
+        if let Some(elapsed) = self.elapsed_at(completed_at) {
+            self.timeline.record_response(
+                elapsed,
+                response.latency,
+                response.request_size,
+                response.response_size,
+                !response.status.is_success(),
+            );
+        }
+    }
+
+    /// 记录一个请求错误
+    pub fn record_error(&mut self, err: &anyhow::Error, completed_at: std::time::Instant) {
+        self.failed += 1;
+        self.errors
+            .entry(error_kind::ErrorKind::classify(err))
+            .or_default()
+            .add(error_kind::message(err));
+        if let Some(elapsed) = self.elapsed_at(completed_at) {
+            self.timeline.record_error(elapsed);
+        }
+    }
+
+    /// 记录批量发送中一个请求的结果, 同时计入所在阶段的统计
+    pub fn record_result(&mut self, stage: usize, result: &Result<HttpResponse>, completed_at: std::time::Instant) {
+        self.sending = self.sending.saturating_sub(1);
+        match result {
+            Ok(response) => {
+                self.record_response(response, completed_at);
+                if let Some(stage_stats) = self.stage_stats.get_mut(stage) {
+                    stage_stats.record_response(response, completed_at);
+                }
+            }
+            Err(err) => {
+                self.record_error(err, completed_at);
+                if let Some(stage_stats) = self.stage_stats.get_mut(stage) {
+                    stage_stats.record_error(err, completed_at);
+                }
+            }
+        }
+    }
+
+    /// 批量发送结束或被取消
+    pub fn finish(&mut self) {
+        self.running = false;
+        self.sending = 0;
+        self.total_end_time = Some(std::time::Instant::now());
+    }
+
+    /// completed_at 相对开始时间的偏移, 不受界面处理事件的延迟影响
+    fn elapsed_at(&self, completed_at: std::time::Instant) -> Option<std::time::Duration> {
+        self.total_start_time.map(|start| completed_at.saturating_duration_since(start))
+    }
+
+    /// 批量发送进度 (0.0 ~ 1.0), 按请求数或时长计算
+    pub fn progress(&self) -> Option<f32> {
+        if let Some(planned) = self.planned_requests.filter(|n| *n > 0) {
+            return Some(self.total_requests() as f32 / planned as f32);
+        }
+        let planned = self.planned_duration.filter(|d| *d > 0.0)?;
+        if !self.running {
+            return Some(1.0);
+        }
+        Some((self.current_duration()? / planned).min(1.0) as f32)
+    }
+
+    /// 当前所在阶段
+    pub fn active_stage(&self) -> Option<usize> {
+        if !self.running {
+            return None;
+        }
+        let now = std::time::Instant::now();
+        self.stage_stats.iter().position(|s| {
+            matches!((s.total_start_time, s.total_end_time), (Some(start), Some(end)) if start <= now && now < end)
+        })
+    }
+
+    /// 阶段 QPS, 阶段进行中时按已经过的时间计算
+    pub fn stage_qps(&self) -> Option<f64> {
+        let (start, end) = (self.total_start_time?, self.total_end_time?);
+        let end = end.min(std::time::Instant::now());
+        let duration = end.checked_duration_since(start)?.as_secs_f64();
+        (duration > 0.0).then(|| self.total_requests() as f64 / duration)
+    }
+
     pub fn total_requests(&self) -> usize {
         self.success + self.failed
     }
@@ -63,44 +208,29 @@ impl RequestStats {
         }
     }
 
-    pub fn min_response_time(&self) -> Option<u128> {
-        self.response_times.iter().min().copied()
+    /// 最小响应时间 (毫秒)
+    pub fn min_response_time(&self) -> Option<f64> {
+        self.latency.min_ms()
     }
 
-    pub fn max_response_time(&self) -> Option<u128> {
-        self.response_times.iter().max().copied()
+    /// 最大响应时间 (毫秒)
+    pub fn max_response_time(&self) -> Option<f64> {
+        self.latency.max_ms()
     }
 
+    /// 平均响应时间 (毫秒)
     pub fn avg_response_time(&self) -> Option<f64> {
-        if self.response_times.is_empty() {
-            None
-        } else {
-            let sum: u128 = self.response_times.iter().sum();
-            Some(sum as f64 / self.response_times.len() as f64)
-        }
+        self.latency.mean_ms()
     }
 
-    pub fn percentile(&self, p: f64) -> Option<u128> {
-        if self.response_times.is_empty() {
-            return None;
-        }
-        let mut sorted = self.response_times.clone();
-        sorted.sort();
-        let index = ((p / 100.0) * sorted.len() as f64).ceil() as usize - 1;
-        sorted.get(index.min(sorted.len() - 1)).copied()
+    /// 每个响应的平均脚本耗时 (毫秒)
+    pub fn avg_script_time(&self) -> Option<f64> {
+        (self.script_runs > 0).then(|| self.script_time.as_secs_f64() * 1000.0 / self.script_runs as f64)
     }
 
-    pub fn add_response_time(&mut self, time: u128) {
-        if self.response_times.len() < self.max_response_times {
-            self.response_times.push(time);
-        } else if self.max_response_times > 0 {
-            use std::collections::hash_map::RandomState;
-            use std::hash::{BuildHasher, Hash, Hasher};
-            let mut hasher = RandomState::new().build_hasher();
-            time.hash(&mut hasher);
-            let idx = (hasher.finish() as usize) % self.max_response_times;
-            self.response_times[idx] = time;
-        }
+    /// 响应时间百分位数 (毫秒)
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        self.latency.percentile_ms(p)
     }
 
     pub fn qps(&self) -> Option<f64> {
@@ -125,11 +255,8 @@ impl RequestStats {
     }
 
     pub fn current_duration(&self) -> Option<f64> {
-        if let Some(start) = self.total_start_time {
-            Some(std::time::Instant::now().duration_since(start).as_secs_f64())
-        } else {
-            None
-        }
+        self.total_start_time
+            .map(|start| std::time::Instant::now().duration_since(start).as_secs_f64())
     }
 
     pub fn upload_throughput_mbps(&self) -> Option<f64> {
@@ -206,6 +333,9 @@ pub struct HttpRequestConfig {
     pub body_raw: String,
     // 原始字符串类型
     pub body_raw_type: RequestBodyRawType,
+    /// 原样发送 body_raw, 不替换其中的 {{var}} (BinaryFile 路径仍会替换)
+    #[serde(default)]
+    pub body_raw_literal: bool,
 
     // 脚本支持
     /// 请求前脚本
@@ -231,6 +361,7 @@ impl Clone for HttpRequestConfig {
             body_form_data: self.body_form_data.clone(),
             body_raw: self.body_raw.clone(),
             body_raw_type: self.body_raw_type.clone(),
+            body_raw_literal: self.body_raw_literal,
             pre_request_script: self.pre_request_script.clone(),
             post_response_script: self.post_response_script.clone(),
             script_enabled: self.script_enabled,
@@ -246,6 +377,7 @@ impl Default for HttpRequestConfig {
             body_tab_ui: RequestBodyTab::Raw,
             body_raw: Default::default(),
             body_raw_type: RequestBodyRawType::Json,
+            body_raw_literal: false,
             query: Default::default(),
             header: Default::default(),
             body_form: Default::default(),
@@ -258,6 +390,15 @@ impl Default for HttpRequestConfig {
 }
 
 impl HttpRequestConfig {
+    /// 替换变量后的 body_raw, BinaryFile 时为文件路径
+    pub fn real_body_raw(&self, vars: &Vec<PairUi>) -> String {
+        if self.body_raw_literal && self.body_raw_type != RequestBodyRawType::BinaryFile {
+            self.body_raw.to_owned()
+        } else {
+            util::parse_var_str(&self.body_raw, vars)
+        }
+    }
+
     pub async fn request_build(&self, vars: &Vec<PairUi>) -> Result<RequestBuilder> {
         let HttpRequestConfig {
             body_tab_ui,
@@ -269,14 +410,13 @@ impl HttpRequestConfig {
         let method = self.method.as_reqwest_method();
 
         // 处理变量
-        let real_url = util::parse_var_str(&url, vars);
+        let real_url = util::parse_var_str(url, vars);
         let request_query = util::real_tuple_vec(&self.query, vars);
         let request_header = util::real_tuple_vec(&self.header, vars);
         let request_body_form = util::real_tuple_vec(&self.body_form, vars);
-        let request_body_form_data = util::real_tuple_vec(&self.body_form_data, vars);
+        let request_body_form_data = util::real_form_data(&self.body_form_data, vars);
 
-        let body_raw = self.body_raw.to_owned();
-        // let body_raw = util::parse_var_str(&self.body_raw, vars);
+        let body_raw = self.real_body_raw(vars);
 
         let client = get_http_client();
 
@@ -288,7 +428,7 @@ impl HttpRequestConfig {
         // add header
         let mut has_content_type = false;
         for (k, v) in &request_header {
-            if k.to_lowercase() == CONTENT_TYPE {
+            if k.eq_ignore_ascii_case(CONTENT_TYPE) {
                 has_content_type = true;
             }
             request_builder = request_builder.header(k, v);
@@ -366,9 +506,31 @@ pub struct HttpTest {
     pub name: String,
     pub tab_ui: RequestTab,
     pub send_count_ui: String,
+    /// 批量发送模式
+    #[serde(default)]
+    pub load_mode: load::LoadMode,
+    /// ConstantRate 模式的目标 RPS
+    #[serde(default)]
+    pub rate_ui: String,
+    /// ConstantRate / Duration 模式的持续时长 (秒)
+    #[serde(default)]
+    pub duration_ui: String,
+    /// Count / Duration 模式的并发数, 为空时使用默认值
+    #[serde(default)]
+    pub concurrency_ui: String,
+    /// Stages 模式的负载阶段
+    #[serde(default)]
+    pub load_profile: load::LoadProfile,
+    /// 数据文件 (CSV 或 JSON), 每行的列作为一次请求的变量
+    #[serde(default)]
+    pub data_file: String,
 
     pub request: HttpRequestConfig,
 
+    /// 响应断言
+    #[serde(default)]
+    pub assertions: Vec<assertion::Assertion>,
+
     #[serde(skip)]
     pub send_count: usize,
 
@@ -381,6 +543,26 @@ pub struct HttpTest {
     #[serde(skip)]
     pub stats: RequestStats,
 
+    /// 进程内唯一的测试 id, 批量发送的事件按它找到所属测试
+    #[serde(skip, default = "load::next_id")]
+    pub id: u64,
+
+    /// 当前批量发送的 id, 不在运行时为 None
+    #[serde(skip)]
+    pub run_id: Option<u64>,
+
+    /// 用于取消正在进行的批量发送
+    #[serde(skip)]
+    pub batch_abort: Option<tokio::task::AbortHandle>,
+
+    /// 本次批量发送使用的数据行
+    #[serde(skip)]
+    pub data_rows: std::sync::Arc<Vec<Vec<PairUi>>>,
+
+    /// Data 模式中每行数据的结果
+    #[serde(skip)]
+    pub iterations: Vec<IterationResult>,
+
     #[serde(skip)]
     pub download_path: String,
 
@@ -388,24 +570,123 @@ pub struct HttpTest {
     pub response_tab_ui: ResponseTab,
 }
 
+/// 解析大于 0 且不超过 max 的数值输入
+fn parse_positive(name: &str, value: &str, max: f64) -> Result<f64> {
+    match value.trim().parse::<f64>() {
+        Ok(v) if v.is_finite() && v > 0.0 && v <= max => Ok(v),
+        _ => bail!("{} \"{}\" 无效, 需要大于 0 且不超过 {} 的数值", name, value.trim(), max),
+    }
+}
+
 impl HttpTest {
-    pub fn send_before_init(&mut self) {
-        self.send_count = self.send_count_ui.parse().unwrap_or(0);
+    /// 批量发送的参数, 设置了数据文件时读取数据行, 相对路径相对于 base_dir
+    pub fn load_plan(&self, base_dir: Option<&std::path::Path>) -> Result<load::LoadPlan> {
+        let data = match self.data_file.trim() {
+            "" => Vec::new(),
+            path => data_file::load_data_file(path, base_dir)?,
+        };
+        // 只有 Count 和 Duration 模式使用并发数, 其他模式中隐藏的输入不做检查
+        let concurrency = match self.concurrency_ui.trim() {
+            "" => load::DEFAULT_CONCURRENCY,
+            v => match v.parse::<usize>() {
+                Ok(n) if n > 0 => n,
+                _ if !matches!(self.load_mode, load::LoadMode::Count | load::LoadMode::Duration) => 0,
+                _ => bail!("并发数 \"{}\" 无效, 需要大于 0 的整数", v),
+            },
+        };
+        if self.load_mode == load::LoadMode::Stages {
+            self.load_profile.validate()?;
+        }
+        // 速率只用于 Rate 模式, 时长用于 Rate 和 Duration 模式
+        let rate = match self.load_mode {
+            load::LoadMode::ConstantRate => parse_positive("速率", &self.rate_ui, load::MAX_RATE)?,
+            _ => 0.0,
+        };
+        let duration = match self.load_mode {
+            load::LoadMode::ConstantRate | load::LoadMode::Duration => {
+                parse_positive("持续时长", &self.duration_ui, load::MAX_DURATION_SECS)?
+            }
+            _ => 0.0,
+        };
+        Ok(load::LoadPlan {
+            mode: self.load_mode.to_owned(),
+            count: self.send_count_ui.trim().parse().unwrap_or(0),
+            concurrency,
+            rate,
+            duration: std::time::Duration::try_from_secs_f64(duration)?,
+            profile: self.load_profile.to_owned(),
+            data: std::sync::Arc::new(data),
+        })
+    }
+
+    /// 开始一次批量发送, 返回本次运行的 id
+    pub fn send_before_init(&mut self, plan: &load::LoadPlan) -> load::RunId {
+        let id = load::RunId {
+            test: self.id,
+            run: load::next_id(),
+        };
+        self.run_id = Some(id.run);
+        self.send_count = plan.total_requests().unwrap_or(0);
         self.response = None;
         self.response_vec.clear();
-        let max_samples = 100000.min(self.send_count);
+        self.iterations.clear();
+        self.data_rows = plan.data.clone();
+        let start = std::time::Instant::now();
+
+        let stage_stats = match plan.mode {
+            load::LoadMode::Stages => plan
+                .profile
+                .stage_offsets()
+                .iter()
+                .zip(plan.profile.stages.iter())
+                .map(|(offset, stage)| {
+                    let stage_start = start + std::time::Duration::from_secs_f64(*offset);
+                    RequestStats {
+                        total_start_time: Some(stage_start),
+                        total_end_time: Some(
+                            stage_start + std::time::Duration::from_secs_f64(stage.duration.max(0.0)),
+                        ),
+                        ..Default::default()
+                    }
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
         self.stats = RequestStats {
             pending: self.send_count,
             sending: 0,
             success: 0,
             failed: 0,
-            response_times: Vec::with_capacity(max_samples),
-            total_start_time: Some(std::time::Instant::now()),
+            assertion_failed: 0,
+            latency: Default::default(),
+            timeline: Default::default(),
+            status_codes: Default::default(),
+            errors: Default::default(),
+            total_start_time: Some(start),
             total_end_time: None,
             total_upload_bytes: 0,
             total_download_bytes: 0,
-            max_response_times: max_samples,
+            running: !plan.is_empty(),
+            planned_requests: plan.total_requests(),
+            planned_duration: plan.total_duration().map(|d| d.as_secs_f64()),
+            stage_stats,
+            script_runs: 0,
+            script_time: Default::default(),
         };
+        id
+    }
+
+    /// 事件是否属于当前的批量发送
+    pub fn is_current_run(&self, id: load::RunId) -> bool {
+        self.id == id.test && self.run_id == Some(id.run)
+    }
+
+    /// 结束当前批量发送, 之后到达的旧事件都会被丢弃
+    pub fn finish_run(&mut self) {
+        self.run_id = None;
+        self.batch_abort = None;
+        self.stats.finish();
     }
     pub fn from_name(name: String) -> Self {
         Self {
@@ -413,6 +694,21 @@ impl HttpTest {
             ..Self::default()
         }
     }
+
+    /// 记录批量发送中一个请求的结果, Data 模式下同时记录该行的结果
+    pub fn record_result(
+        &mut self,
+        stage: usize,
+        row: Option<usize>,
+        result: &Result<HttpResponse>,
+        completed_at: std::time::Instant,
+    ) {
+        self.stats.record_result(stage, result, completed_at);
+        if let (Some(row), load::LoadMode::Data) = (row, &self.load_mode) {
+            let vars = self.data_rows.get(row).cloned().unwrap_or_default();
+            self.iterations.push(IterationResult::new(row, vars, result));
+        }
+    }
 }
 
 impl Clone for HttpTest {
@@ -423,11 +719,23 @@ impl Clone for HttpTest {
             response: None,
             response_tab_ui: self.response_tab_ui.to_owned(),
             request: self.request.to_owned(),
+            assertions: self.assertions.to_owned(),
             download_path: Default::default(),
             response_vec: Default::default(),
             send_count_ui: self.send_count_ui.to_owned(),
+            load_mode: self.load_mode.to_owned(),
+            rate_ui: self.rate_ui.to_owned(),
+            duration_ui: self.duration_ui.to_owned(),
+            concurrency_ui: self.concurrency_ui.to_owned(),
+            load_profile: self.load_profile.to_owned(),
             send_count: 0,
             stats: Default::default(),
+            id: load::next_id(),
+            run_id: None,
+            batch_abort: None,
+            data_file: self.data_file.to_owned(),
+            data_rows: Default::default(),
+            iterations: Default::default(),
         }
     }
 }
@@ -441,10 +749,78 @@ impl Default for HttpTest {
             tab_ui: RequestTab::Params,
             response_tab_ui: ResponseTab::Data,
             request: HttpRequestConfig::default(),
+            assertions: Default::default(),
             response_vec: Default::default(),
             send_count_ui: String::from("1"),
+            load_mode: Default::default(),
+            rate_ui: Default::default(),
+            duration_ui: Default::default(),
+            concurrency_ui: Default::default(),
+            load_profile: Default::default(),
             stats: Default::default(),
             send_count: 0,
+            id: load::next_id(),
+            run_id: None,
+            batch_abort: None,
+            data_file: Default::default(),
+            data_rows: Default::default(),
+            iterations: Default::default(),
+        }
+    }
+}
+
+/// Data 模式中一行数据的结果
+#[derive(Debug, Clone)]
+pub struct IterationResult {
+    /// 数据行下标
+    pub row: usize,
+    /// 该行的变量
+    pub vars: Vec<PairUi>,
+    /// HTTP 状态码, 请求失败时为 None
+    pub status: Option<u16>,
+    /// 响应时间 (ms)
+    pub duration: u128,
+    /// 请求错误信息
+    pub error: Option<String>,
+    /// 断言和脚本 test() 结果
+    pub assertions: Vec<assertion::AssertionResult>,
+}
+
+impl IterationResult {
+    pub fn new(row: usize, vars: Vec<PairUi>, result: &Result<HttpResponse>) -> Self {
+        let mut iteration = Self {
+            row,
+            vars,
+            status: None,
+            duration: 0,
+            error: None,
+            assertions: Vec::new(),
+        };
+        match result {
+            Ok(response) => {
+                iteration.status = Some(response.status.as_u16());
+                iteration.duration = response.duration;
+                iteration.assertions = response
+                    .assertion_results
+                    .iter()
+                    .chain(response.script_tests.iter())
+                    .cloned()
+                    .collect();
+            }
+            Err(err) => iteration.error = Some(error_kind::message(err)),
+        }
+        iteration
+    }
+
+    /// 配置了断言时以断言为准, 否则要求 2xx
+    pub fn passed(&self) -> bool {
+        if self.error.is_some() {
+            return false;
+        }
+        if self.assertions.is_empty() {
+            self.status.is_some_and(|s| (200..300).contains(&s))
+        } else {
+            self.assertions.iter().all(|r| r.passed)
         }
     }
 }
@@ -455,30 +831,91 @@ pub struct HttpResponse {
     pub headers_str: String,
     pub version: reqwest::Version,
     pub status: reqwest::StatusCode,
+    /// 请求发出的时间
+    pub started_at: std::time::SystemTime,
     pub img: Option<()>,
     pub text: Option<String>,
     pub data_vec: Option<Vec<u8>>,
+    /// 响应时间 (毫秒)
     pub duration: u128,
+    /// 响应时间 (精确值), 用于统计
+    pub latency: std::time::Duration,
     pub request_size: u64,
     pub response_size: u64,
+    /// 实际发出的请求
+    pub request: SentRequest,
+    /// 读取响应体的耗时, latency 只计到收到响应头
+    pub receive_time: std::time::Duration,
     /// 脚本修改后的环境变量
     pub modified_vars: Option<Vec<PairUi>>,
+    /// 断言结果
+    pub assertion_results: Vec<assertion::AssertionResult>,
+    /// 脚本 test() 结果
+    pub script_tests: Vec<assertion::AssertionResult>,
+    /// 脚本执行错误
+    pub script_errors: Vec<String>,
+    /// 脚本 console_log 输出和错误
+    pub console: Vec<script_engine::ConsoleLine>,
+    /// 请求前脚本耗时
+    pub pre_script_time: Option<std::time::Duration>,
+    /// 响应后脚本耗时
+    pub post_script_time: Option<std::time::Duration>,
+}
+
+/// 实际发出的请求: 已替换变量并应用了请求前脚本的修改
+#[derive(Debug, Clone, Default)]
+pub struct SentRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    /// 请求体, multipart 等流式请求体为 None
+    pub body: Option<Vec<u8>>,
+}
+
+impl SentRequest {
+    pub fn from_request(request: &reqwest::Request) -> Self {
+        Self {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            headers: request
+                .headers()
+                .iter()
+                .map(|(k, v)| (k.as_str().to_owned(), v.to_str().unwrap_or_default().to_owned()))
+                .collect(),
+            body: request.body().and_then(|b| b.as_bytes()).map(|b| b.to_vec()),
+        }
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
 }
 
 impl HttpResponse {
+    /// 所有断言和脚本测试是否通过
+    pub fn assertions_passed(&self) -> bool {
+        self.assertion_results
+            .iter()
+            .chain(self.script_tests.iter())
+            .all(|r| r.passed)
+    }
+
     pub fn content_type(&self) -> Option<&str> {
         self.headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok())
     }
 
     pub fn content_type_image(&self) -> bool {
         self.content_type()
-            .and_then(|v| Some(v.starts_with("image/")))
+            .map(|v| v.starts_with("image/"))
             .unwrap_or(false)
     }
 
     pub fn content_type_json(&self) -> bool {
         self.content_type()
-            .and_then(|v| Some(v.contains(APPLICATION_JSON)))
+            .map(|v| v.contains(APPLICATION_JSON))
             .unwrap_or(false)
     }
 }
@@ -512,35 +949,29 @@ impl PairUi {
     }
 }
 
-#[derive(Debug, strum::AsRefStr, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, strum::AsRefStr, Clone, PartialEq, Serialize, Deserialize)]
 pub enum RequestTab {
+    #[default]
     Params,
     Headers,
     Body,
     Scripts,
-}
-impl Default for RequestTab {
-    fn default() -> Self {
-        RequestTab::Params
-    }
+    Assertions,
+    Data,
 }
 
-#[derive(Debug, strum::AsRefStr, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, strum::AsRefStr, Clone, PartialEq, Serialize, Deserialize)]
 pub enum RequestBodyTab {
+    #[default]
     Raw,
     Form,
     FormData,
 }
 
-impl Default for RequestBodyTab {
-    fn default() -> Self {
-        RequestBodyTab::Raw
-    }
-}
-
-#[derive(Debug, strum::AsRefStr, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, strum::AsRefStr, Clone, PartialEq, Serialize, Deserialize)]
 pub enum RequestBodyRawType {
     /// 出入json文本
+    #[default]
     Json,
     /// 字符串文本
     Text,
@@ -552,28 +983,22 @@ pub enum RequestBodyRawType {
     /// 本地文件路径，或则http/https开始的文件
     BinaryFile,
 }
-impl Default for RequestBodyRawType {
-    fn default() -> Self {
-        RequestBodyRawType::Json
-    }
-}
 
-#[derive(Debug, strum::AsRefStr, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, strum::AsRefStr, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ResponseTab {
+    #[default]
     Data,
     Header,
     Stats,
+    Assertions,
+    Iterations,
+    Console,
 }
 
-impl Default for ResponseTab {
-    fn default() -> Self {
-        ResponseTab::Data
-    }
-}
-
-#[derive(Debug, strum::AsRefStr, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, strum::AsRefStr, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Method {
     OPTIONS,
+    #[default]
     GET,
     POST,
     PUT,
@@ -586,14 +1011,25 @@ pub enum Method {
 }
 
 impl Method {
-    pub fn as_reqwest_method(&self) -> reqwest::Method {
-        reqwest::Method::from_bytes(self.as_ref().as_bytes()).unwrap()
+    /// 按名称 (不区分大小写) 匹配请求方法
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_uppercase().as_str() {
+            "OPTIONS" => Method::OPTIONS,
+            "GET" => Method::GET,
+            "POST" => Method::POST,
+            "PUT" => Method::PUT,
+            "DELETE" => Method::DELETE,
+            "HEAD" => Method::HEAD,
+            "TRACE" => Method::TRACE,
+            "CONNECT" => Method::CONNECT,
+            "PATCH" => Method::PATCH,
+            "WS" => Method::WS,
+            _ => return None,
+        })
     }
-}
 
-impl Default for Method {
-    fn default() -> Self {
-        Method::GET
+    pub fn as_reqwest_method(&self) -> reqwest::Method {
+        reqwest::Method::from_bytes(self.as_ref().as_bytes()).unwrap()
     }
 }
 
@@ -601,6 +1037,9 @@ impl Default for Method {
 pub struct Group {
     pub name: String,
     pub childrent: Vec<HttpTest>,
+    /// 组内每个请求都执行的脚本
+    #[serde(default)]
+    pub scripts: script_engine::SharedScripts,
 
     #[serde(skip)]
     pub new_child_name: String,
@@ -611,6 +1050,7 @@ impl Group {
         Group {
             name,
             childrent: Default::default(),
+            scripts: Default::default(),
             new_child_name: Default::default(),
         }
     }
@@ -643,11 +1083,142 @@ impl AppConfig {
     }
 }
 
+/// 命名环境, 如 dev / staging / local
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Environment {
+    pub name: String,
+    pub variables: Vec<PairUi>,
+}
+
+impl Environment {
+    pub fn from_name(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            variables: Default::default(),
+        }
+    }
+}
+
+/// 可导入的外部格式
+#[derive(Debug, Default, strum::AsRefStr, Clone, PartialEq)]
+pub enum ImportFormat {
+    #[default]
+    #[strum(serialize = "Postman Collection")]
+    PostmanCollection,
+    #[strum(serialize = "Postman Environment")]
+    PostmanEnvironment,
+    #[strum(serialize = "OpenAPI / Swagger")]
+    OpenApi,
+    #[strum(serialize = "HAR")]
+    Har,
+    #[strum(serialize = ".http / .rest")]
+    HttpFile,
+}
+
+impl ImportFormat {
+    pub fn import_file(&self, path: &str) -> Result<ImportResult> {
+        let path = std::path::Path::new(path);
+        let data = std::fs::read_to_string(path)?;
+        match self {
+            ImportFormat::HttpFile => {
+                let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("http");
+                http_file::parse_http_file(name, &data, path.parent())
+            }
+            _ => self.import(&data),
+        }
+    }
+
+    pub fn import(&self, data: &str) -> Result<ImportResult> {
+        match self {
+            ImportFormat::PostmanCollection => postman::import_collection(data),
+            ImportFormat::PostmanEnvironment => Ok(ImportResult {
+                environments: vec![postman::import_environment(data)?],
+                ..Default::default()
+            }),
+            ImportFormat::OpenApi => openapi::import_openapi(data),
+            ImportFormat::Har => har::import_har(data),
+            ImportFormat::HttpFile => http_file::parse_http_file("http", data, None),
+        }
+    }
+}
+
+/// 可导出的外部格式
+#[derive(Debug, Default, strum::AsRefStr, Clone, PartialEq)]
+pub enum ExportFormat {
+    #[default]
+    #[strum(serialize = "HAR")]
+    Har,
+    /// 每个组写一个 .http 文件到目录
+    #[strum(serialize = ".http")]
+    HttpFile,
+}
+
+impl ExportFormat {
+    pub fn export_to(&self, project: &Project, path: &str) -> Result<()> {
+        match self {
+            ExportFormat::Har => std::fs::write(path, har::export_har(project)?)?,
+            ExportFormat::HttpFile => {
+                let dir = std::path::Path::new(path);
+                std::fs::create_dir_all(dir)?;
+                let vars = project.resolved_variables();
+                let mut used = std::collections::HashSet::new();
+                for group in &project.groups {
+                    let base: String = group
+                        .name
+                        .chars()
+                        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+                        .collect();
+                    let base = if base.is_empty() { "group".to_owned() } else { base };
+                    // 不同组名清理后可能相同, 添加序号避免互相覆盖 (按小写比较, 兼容不区分大小写的文件系统)
+                    let mut file_name = base.clone();
+                    let mut n = 2;
+                    while !used.insert(file_name.to_lowercase()) {
+                        file_name = format!("{}_{}", base, n);
+                        n += 1;
+                    }
+                    std::fs::write(
+                        dir.join(format!("{}.http", file_name)),
+                        http_file::write_http_file(group, &vars),
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 从外部格式 (Postman / OpenAPI 等) 导入的内容
+#[derive(Clone, Default)]
+pub struct ImportResult {
+    pub groups: Vec<Group>,
+    /// 合并到全局变量
+    pub variables: Vec<PairUi>,
+    pub environments: Vec<Environment>,
+    /// 未能完整转换的内容
+    pub warnings: Vec<String>,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Project {
     pub name: String,
     pub groups: Vec<Group>,
+    /// 全局变量 (旧版本项目中的变量都在这里)
     pub variables: Vec<PairUi>,
+    /// 命名环境
+    #[serde(default)]
+    pub environments: Vec<Environment>,
+    /// 当前环境在 environments 中的下标
+    #[serde(default)]
+    pub active_environment: Option<usize>,
+    /// 项目中每个请求都执行的脚本
+    #[serde(default)]
+    pub scripts: script_engine::SharedScripts,
+    /// 脚本中可以 import 的模块
+    #[serde(default)]
+    pub modules: Vec<script_engine::ScriptModule>,
+    /// 项目文件所在目录, 用于查找 .rhai 模块文件
+    #[serde(skip)]
+    pub dir: Option<std::path::PathBuf>,
 }
 
 impl Project {
@@ -656,6 +1227,127 @@ impl Project {
             name: name.to_owned(),
             groups: Default::default(),
             variables: Default::default(),
+            environments: Default::default(),
+            active_environment: None,
+            scripts: Default::default(),
+            modules: Default::default(),
+            dir: None,
+        }
+    }
+
+    /// 脚本可导入的模块: 项目中保存的模块和项目目录下的 .rhai 文件
+    pub fn script_library(&self) -> script_engine::ScriptLibrary {
+        script_engine::ScriptLibrary {
+            modules: self.modules.clone(),
+            base_dir: self.dir.clone(),
+        }
+    }
+
+    /// 按 项目 → 组 → 请求 的顺序编译请求要执行的脚本
+    pub fn compile_scripts(&self, group: &Group, cfg: &HttpRequestConfig) -> script_engine::CompiledScripts {
+        use script_engine::ScriptScope;
+        script_engine::CompiledScripts::compile_with(
+            &self.script_library(),
+            &[(ScriptScope::Project, &self.scripts), (ScriptScope::Group, &group.scripts)],
+            cfg,
+        )
+    }
+
+    pub fn active_environment(&self) -> Option<&Environment> {
+        self.active_environment.and_then(|i| self.environments.get(i))
+    }
+
+    /// 按名称切换环境
+    pub fn select_environment(&mut self, name: &str) -> Result<()> {
+        match self.environments.iter().position(|e| e.name == name) {
+            Some(i) => {
+                self.active_environment = Some(i);
+                Ok(())
+            }
+            None => bail!("环境不存在: {}", name),
+        }
+    }
+
+    pub fn remove_environment(&mut self, index: usize) {
+        if index >= self.environments.len() {
+            return;
+        }
+        self.environments.remove(index);
+        self.active_environment = match self.active_environment {
+            Some(i) if i == index => None,
+            Some(i) if i > index => Some(i - 1),
+            other => other,
+        };
+    }
+
+    /// 请求实际使用的变量
+    ///
+    /// 优先级: 当前环境 > 全局变量, 同名变量只保留优先级最高且启用的一个
+    pub fn resolved_variables(&self) -> Vec<PairUi> {
+        let env_vars = self.active_environment().map(|e| e.variables.as_slice()).unwrap_or(&[]);
+        let mut vars: Vec<PairUi> = Vec::new();
+        for var in env_vars.iter().chain(self.variables.iter()) {
+            if !var.bad() && !vars.iter().any(|v| v.key == var.key) {
+                vars.push(var.clone());
+            }
+        }
+        vars
+    }
+
+    /// 合并导入内容: 组名重复时添加序号, 已存在的全局变量保持不变, 同名环境被替换
+    ///
+    /// 没有当前环境时, 导入的第一个环境成为当前环境, 使其中的变量覆盖同名全局变量
+    pub fn merge_import(&mut self, result: ImportResult) {
+        for mut group in result.groups {
+            let base = group.name.clone();
+            let mut n = 2;
+            while self.groups.iter().any(|g| g.name == group.name) {
+                group.name = format!("{} ({})", base, n);
+                n += 1;
+            }
+            self.groups.push(group);
+        }
+        for var in result.variables {
+            if !self.variables.iter().any(|v| v.key == var.key) {
+                self.variables.push(var);
+            }
+        }
+        for env in result.environments {
+            let index = match self.environments.iter().position(|e| e.name == env.name) {
+                Some(i) => {
+                    self.environments[i] = env;
+                    i
+                }
+                None => {
+                    self.environments.push(env);
+                    self.environments.len() - 1
+                }
+            };
+            self.active_environment.get_or_insert(index);
+        }
+    }
+
+    /// 将脚本修改后的变量写回: 已存在则更新原处, 否则添加到当前环境 (无环境时添加到全局)
+    pub fn apply_modified_vars(&mut self, modified: &[PairUi]) {
+        for var in modified {
+            let env_vars = match self.active_environment {
+                Some(i) => self.environments.get_mut(i).map(|e| &mut e.variables),
+                None => None,
+            };
+            if let Some(existing) = env_vars
+                .and_then(|vars| vars.iter_mut().find(|v| !v.bad() && v.key == var.key))
+            {
+                existing.value = var.value.clone();
+                continue;
+            }
+            if let Some(existing) = self.variables.iter_mut().find(|v| !v.bad() && v.key == var.key) {
+                existing.value = var.value.clone();
+                continue;
+            }
+            match self.active_environment.and_then(|i| self.environments.get_mut(i)) {
+                Some(env) => env.variables.push(var.clone()),
+                None => self.variables.push(var.clone()),
+            }
         }
     }
 }
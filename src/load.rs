@@ -0,0 +1,374 @@
+//! 批量发送 (压测)
+//!
+//! - Count: 闭环模型, 保持指定并发数的请求在途, 直到发送完指定次数
+//! - Duration: 闭环模型, 保持指定并发数的请求在途, 持续指定时长
+//! - ConstantRate: 开环模型, 按固定时间表以目标 RPS 发送, 与响应快慢无关,
+//!   延迟从计划发送时间开始计算, 避免协调遗漏 (coordinated omission)
+//! - Stages: 多阶段负载, 每个阶段在持续时间内从上一阶段的目标线性变化到本阶段的目标,
+//!   目标可以是并发数 (闭环) 或 RPS (开环)
+//! - Data: 按顺序为数据文件的每一行发送一次请求, 记录每行的结果
+//!
+//! 设置了数据文件时, 其他模式按发送顺序循环使用数据行作为变量
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::stream::{FuturesUnordered, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::assertion::{self, Assertion};
+use crate::script_engine::CompiledScripts;
+use crate::{util, HttpRequestConfig, HttpResponse, PairUi};
+
+/// 未设置并发数时同时在途的最大请求数
+pub const DEFAULT_CONCURRENCY: usize = 10000;
+
+/// 持续时长上限 (秒), 与阶段编辑器的上限一致
+pub const MAX_DURATION_SECS: f64 = 86400.0;
+
+/// 每秒请求数上限
+pub const MAX_RATE: f64 = 1_000_000.0;
+
+/// 阶段目标值 (并发数或每秒请求数) 上限, 与阶段编辑器的上限一致
+pub const MAX_STAGE_TARGET: f64 = 1_000_000.0;
+
+/// 并发阶段重新计算目标并发数的间隔
+const STAGE_TICK: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Default, strum::AsRefStr, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LoadMode {
+    /// 发送指定次数
+    #[default]
+    Count,
+    /// 固定速率 (RPS) 持续指定时长
+    #[strum(serialize = "Rate")]
+    ConstantRate,
+    /// 固定并发数持续指定时长
+    Duration,
+    /// 多阶段负载
+    Stages,
+    /// 数据文件每行发送一次
+    Data,
+}
+
+/// 阶段目标的含义
+#[derive(Debug, Default, strum::AsRefStr, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ProfileKind {
+    /// 同时在途的请求数
+    #[default]
+    Concurrency,
+    /// 每秒请求数
+    Rate,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoadStage {
+    /// 持续时长 (秒)
+    pub duration: f64,
+    /// 阶段结束时的目标值
+    pub target: f64,
+}
+
+/// 多阶段负载, 第一阶段从 0 开始变化
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoadProfile {
+    pub kind: ProfileKind,
+    pub stages: Vec<LoadStage>,
+}
+
+impl LoadProfile {
+    /// 检查阶段设置, 项目文件中的阶段未经过编辑器的范围限制
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.stages.is_empty() {
+            anyhow::bail!("没有负载阶段");
+        }
+        for (i, stage) in self.stages.iter().enumerate() {
+            if !(0.0..=MAX_DURATION_SECS).contains(&stage.duration) {
+                anyhow::bail!("阶段 {} 的时长 {:?} 无效, 需要在 0 到 {} 秒之间", i + 1, stage.duration, MAX_DURATION_SECS);
+            }
+            if !(0.0..=MAX_STAGE_TARGET).contains(&stage.target) {
+                anyhow::bail!("阶段 {} 的目标值 {:?} 无效, 需要在 0 到 {} 之间", i + 1, stage.target, MAX_STAGE_TARGET);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn total_duration(&self) -> f64 {
+        self.stages.iter().map(|s| s.duration.max(0.0)).sum()
+    }
+
+    /// 各阶段的开始时间 (秒)
+    pub fn stage_offsets(&self) -> Vec<f64> {
+        self.stages
+            .iter()
+            .scan(0.0, |offset, stage| {
+                let start = *offset;
+                *offset += stage.duration.max(0.0);
+                Some(start)
+            })
+            .collect()
+    }
+
+    /// elapsed 秒时的 (阶段下标, 目标值), 结束后为 None
+    pub fn target_at(&self, elapsed: f64) -> Option<(usize, f64)> {
+        let mut from = 0.0;
+        let mut start = 0.0;
+        for (i, stage) in self.stages.iter().enumerate() {
+            let duration = stage.duration.max(0.0);
+            if elapsed < start + duration {
+                let progress = (elapsed - start) / duration;
+                return Some((i, from + (stage.target - from) * progress));
+            }
+            from = stage.target;
+            start += duration;
+        }
+        None
+    }
+
+    /// Rate 阶段中第 n 个请求 (从 0 开始) 的计划发送时间 (秒) 和阶段下标
+    ///
+    /// 在每个阶段内速率线性变化, 累计请求数为 r0*t + (r1-r0)*t²/(2d), 对其求逆
+    fn request_time(&self, n: usize) -> Option<(usize, f64)> {
+        // 第 n 个请求在累计请求数达到 n 时发送
+        let mut remaining = n as f64;
+        let mut from = 0.0_f64;
+        let mut start = 0.0;
+        for (i, stage) in self.stages.iter().enumerate() {
+            let (r0, r1, d) = (from.max(0.0), stage.target.max(0.0), stage.duration.max(0.0));
+            let count = (r0 + r1) / 2.0 * d;
+            if remaining < count {
+                let a = (r1 - r0) / (2.0 * d);
+                let t = if a.abs() < f64::EPSILON {
+                    remaining / r0
+                } else {
+                    (-r0 + (r0 * r0 + 4.0 * a * remaining).max(0.0).sqrt()) / (2.0 * a)
+                };
+                return Some((i, start + t.clamp(0.0, d)));
+            }
+            remaining -= count;
+            from = stage.target;
+            start += d;
+        }
+        None
+    }
+}
+
+/// 一次批量发送的参数
+#[derive(Debug, Clone)]
+pub struct LoadPlan {
+    pub mode: LoadMode,
+    pub count: usize,
+    /// Count / Duration 模式的并发数
+    pub concurrency: usize,
+    /// 每秒请求数
+    pub rate: f64,
+    pub duration: Duration,
+    pub profile: LoadProfile,
+    /// 数据文件的行, 每行为一组变量
+    pub data: Arc<Vec<Vec<PairUi>>>,
+}
+
+impl LoadPlan {
+    /// 计划发送的请求总数, 无法预先确定时为 None
+    pub fn total_requests(&self) -> Option<usize> {
+        match self.mode {
+            LoadMode::Count => Some(self.count),
+            LoadMode::ConstantRate => Some((self.rate * self.duration.as_secs_f64()).floor() as usize),
+            LoadMode::Data => Some(self.data.len()),
+            LoadMode::Duration | LoadMode::Stages => None,
+        }
+    }
+
+    /// 计划持续时长, 无法预先确定时为 None
+    pub fn total_duration(&self) -> Option<Duration> {
+        match self.mode {
+            LoadMode::Count | LoadMode::Data => None,
+            LoadMode::ConstantRate | LoadMode::Duration => Some(self.duration),
+            LoadMode::Stages => Some(Duration::from_secs_f64(self.profile.total_duration())),
+        }
+    }
+
+    /// 没有要发送的请求
+    pub fn is_empty(&self) -> bool {
+        match self.mode {
+            LoadMode::Count => self.count == 0 || self.concurrency == 0,
+            LoadMode::ConstantRate => self.total_requests() == Some(0),
+            LoadMode::Duration => self.duration.is_zero() || self.concurrency == 0,
+            LoadMode::Stages => self.profile.total_duration() <= 0.0,
+            LoadMode::Data => self.data.is_empty(),
+        }
+    }
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 进程内唯一的 id, 用于标识测试和批量发送
+pub fn next_id() -> u64 {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// 一次批量发送的标识, 事件按 test 找到所属测试, run 与测试当前运行不一致的事件直接丢弃
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunId {
+    pub test: u64,
+    pub run: u64,
+}
+
+/// 批量发送过程中产生的事件
+// 绝大多数事件都是 Response, 装箱只会增加每个请求的分配
+#[allow(clippy::large_enum_variant)]
+pub enum LoadEvent {
+    /// 一个请求的结果 (已计算断言), stage 为发送时所在的阶段, row 为使用的数据行
+    ///
+    /// completed_at 为请求完成的时间, 统计按它归入时间线, 而不是界面取出事件的时间
+    Response {
+        id: RunId,
+        stage: usize,
+        row: Option<usize>,
+        completed_at: std::time::Instant,
+        result: anyhow::Result<HttpResponse>,
+    },
+    /// 全部请求已完成
+    Finished { id: RunId },
+}
+
+/// 按计划发送请求, 结果和结束事件带上 id 发送到 tx
+///
+/// scripts 由调用方预先编译, 所有请求共享
+///
+/// 取消时直接中止运行该 future 的任务, 在途请求随之被丢弃, 不会再发送 Finished
+pub async fn run(
+    id: RunId,
+    cfg: Arc<HttpRequestConfig>,
+    vars: Arc<Vec<PairUi>>,
+    assertions: Arc<Vec<Assertion>>,
+    scripts: Arc<CompiledScripts>,
+    plan: LoadPlan,
+    tx: mpsc::Sender<LoadEvent>,
+) {
+    let mut futures = FuturesUnordered::new();
+    let mut sent = 0;
+
+    // 每个数据行与项目变量合并后的变量
+    let row_vars: Vec<Arc<Vec<PairUi>>> = plan
+        .data
+        .iter()
+        .map(|row| {
+            let mut merged = vars.to_vec();
+            util::merge_vars(&mut merged, row);
+            Arc::new(merged)
+        })
+        .collect();
+    // 请求序号, 用于 {{$iteration}} 和按顺序循环取数据行
+    let next_iteration = AtomicUsize::new(0);
+
+    let base_vars = vars.clone();
+    let send = |stage: usize, intended: Option<Instant>| {
+        let cfg = cfg.clone();
+        let iteration = next_iteration.fetch_add(1, Ordering::Relaxed);
+        let (row, vars) = if row_vars.is_empty() {
+            (None, vars.clone())
+        } else {
+            let row = iteration % row_vars.len();
+            (Some(row), row_vars[row].clone())
+        };
+        let base_vars = base_vars.clone();
+        let data = plan.data.clone();
+        let scripts = scripts.clone();
+        let assertions = assertions.clone();
+        let tx = tx.clone();
+        util::ITERATION.scope(iteration, async move {
+            let result = util::http_send_with_scripts(&cfg, &vars, &scripts).await.map(|mut response| {
+                if let (Some(modified), Some(row)) = (&mut response.modified_vars, row) {
+                    util::drop_row_vars(modified, &data[row], &base_vars);
+                }
+                if let Some(intended) = intended {
+                    response.latency = intended.elapsed();
+                    response.duration = response.latency.as_millis();
+                }
+                response.assertion_results = assertion::evaluate_all(&assertions, &response);
+                response
+            });
+            let completed_at = std::time::Instant::now();
+            let _ = tx.send(LoadEvent::Response { id, stage, row, completed_at, result }).await;
+        })
+    };
+
+    let start = Instant::now();
+    match plan.mode {
+        LoadMode::Count => {
+            let total = plan.count;
+            while sent < total || !futures.is_empty() {
+                while sent < total && futures.len() < plan.concurrency {
+                    futures.push(send(0, None));
+                    sent += 1;
+                }
+                futures.next().await;
+            }
+        }
+        LoadMode::Duration => {
+            let deadline = start + plan.duration;
+            while Instant::now() < deadline {
+                while futures.len() < plan.concurrency {
+                    futures.push(send(0, None));
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep_until(deadline) => {}
+                    Some(_) = futures.next() => {}
+                }
+            }
+        }
+        LoadMode::ConstantRate => {
+            let total = plan.total_requests().unwrap_or(0);
+            while sent < total {
+                // 落后于计划时立即补发, 而不是顺延时间表
+                let intended = start + Duration::from_secs_f64(sent as f64 / plan.rate);
+                tokio::select! {
+                    _ = tokio::time::sleep_until(intended) => {
+                        futures.push(send(0, Some(intended)));
+                        sent += 1;
+                    }
+                    Some(_) = futures.next(), if !futures.is_empty() => {}
+                }
+            }
+        }
+        LoadMode::Data => {
+            // 按行顺序逐个发送, 结果与数据行一一对应
+            for _ in 0..plan.data.len() {
+                send(0, None).await;
+            }
+        }
+        LoadMode::Stages => match plan.profile.kind {
+            ProfileKind::Concurrency => {
+                while let Some((stage, target)) = plan.profile.target_at(start.elapsed().as_secs_f64()) {
+                    // 目标下降时不补充, 等在途请求自然完成
+                    while futures.len() < target.round() as usize {
+                        futures.push(send(stage, None));
+                    }
+                    tokio::select! {
+                        _ = tokio::time::sleep(STAGE_TICK) => {}
+                        Some(_) = futures.next(), if !futures.is_empty() => {}
+                    }
+                }
+            }
+            ProfileKind::Rate => {
+                while let Some((stage, at)) = plan.profile.request_time(sent) {
+                    let intended = start + Duration::from_secs_f64(at);
+                    tokio::select! {
+                        _ = tokio::time::sleep_until(intended) => {
+                            futures.push(send(stage, Some(intended)));
+                            sent += 1;
+                        }
+                        Some(_) = futures.next(), if !futures.is_empty() => {}
+                    }
+                }
+            }
+        },
+    }
+
+    while futures.next().await.is_some() {}
+    let _ = tx.send(LoadEvent::Finished { id }).await;
+}
@@ -6,7 +6,6 @@ use core::f32;
 use std::time::Duration;
 use futures_util::{SinkExt, StreamExt};
 use std::collections::BTreeMap;
-use futures::stream::{FuturesUnordered, StreamExt as FuturesStreamExt};
 use std::io::Read;
 use num_format::{Locale, ToFormattedString};
 use std::ops::Index;
@@ -18,6 +17,7 @@ use tokio_tungstenite::{connect_async, tungstenite};
 // use tungstenite::{self, http, Message};
 
 use api_test_rs::*;
+use api_test_rs::script_engine::{CompiledScripts, ScriptLibrary, ScriptModule, ScriptScope};
 use eframe::egui::style::Selection;
 use eframe::egui::{self, global_theme_preference_buttons};
 use eframe::egui::{CollapsingHeader, FontFamily, FontId, TextEdit, TextStyle, Theme};
@@ -44,7 +44,14 @@ const METHODS: [Method; 10] = [
     Method::PATCH,
     Method::WS,
 ];
-const REQ_TABS: [RequestTab; 4] = [RequestTab::Params, RequestTab::Headers, RequestTab::Body, RequestTab::Scripts];
+const REQ_TABS: [RequestTab; 6] = [
+    RequestTab::Params,
+    RequestTab::Headers,
+    RequestTab::Body,
+    RequestTab::Scripts,
+    RequestTab::Assertions,
+    RequestTab::Data,
+];
 const REQ_BODY_TABS: [RequestBodyTab; 3] = [
     RequestBodyTab::Raw,
     RequestBodyTab::Form,
@@ -59,8 +66,48 @@ const REQ_BODY_RAW_TYPES: [RequestBodyRawType; 5] = [
 ];
 const WS_BODY_RAW_TYPES: [RequestBodyRawType; 2] =
     [RequestBodyRawType::Text, RequestBodyRawType::BinaryFile];
+const SNIPPET_LANGS: [snippet::SnippetLang; 4] = [
+    snippet::SnippetLang::Curl,
+    snippet::SnippetLang::RustReqwest,
+    snippet::SnippetLang::PythonRequests,
+    snippet::SnippetLang::JavaScriptFetch,
+];
+const IMPORT_FORMATS: [ImportFormat; 5] = [
+    ImportFormat::PostmanCollection,
+    ImportFormat::PostmanEnvironment,
+    ImportFormat::OpenApi,
+    ImportFormat::Har,
+    ImportFormat::HttpFile,
+];
+const EXPORT_FORMATS: [ExportFormat; 2] = [ExportFormat::Har, ExportFormat::HttpFile];
+const LOAD_MODES: [load::LoadMode; 5] = [
+    load::LoadMode::Count,
+    load::LoadMode::ConstantRate,
+    load::LoadMode::Duration,
+    load::LoadMode::Stages,
+    load::LoadMode::Data,
+];
+const PROFILE_KINDS: [load::ProfileKind; 2] = [load::ProfileKind::Concurrency, load::ProfileKind::Rate];
 const COLUMN_WIDTH_INITIAL: f32 = 200.0;
-const RESPONSE_TABS: [ResponseTab; 3] = [ResponseTab::Data, ResponseTab::Header, ResponseTab::Stats];
+const RESPONSE_TABS: [ResponseTab; 6] = [
+    ResponseTab::Data,
+    ResponseTab::Header,
+    ResponseTab::Stats,
+    ResponseTab::Assertions,
+    ResponseTab::Iterations,
+    ResponseTab::Console,
+];
+const ASSERTION_KINDS: [assertion::AssertionKind; 9] = [
+    assertion::AssertionKind::StatusEquals,
+    assertion::AssertionKind::StatusInRange,
+    assertion::AssertionKind::HeaderExists,
+    assertion::AssertionKind::HeaderMatches,
+    assertion::AssertionKind::BodyContains,
+    assertion::AssertionKind::JsonPathEquals,
+    assertion::AssertionKind::ResponseTimeBelow,
+    assertion::AssertionKind::BodySizeBelow,
+    assertion::AssertionKind::BodySizeAbove,
+];
 /* #endregion */
 
 fn main() -> eframe::Result {
@@ -145,8 +192,8 @@ struct ApiTestApp {
     ws_tx: Option<tokio::sync::mpsc::Sender<WsMessage>>,
     ws_messages: Arc<std::sync::RwLock<Vec<Message>>>,
 
-    http_tx: mpsc::Sender<Result<HttpResponse>>,
-    http_rx: mpsc::Receiver<Result<HttpResponse>>,
+    http_tx: mpsc::Sender<load::LoadEvent>,
+    http_rx: mpsc::Receiver<load::LoadEvent>,
 
     // 加载保存的项目文件路径
     project_path: String,
@@ -158,6 +205,23 @@ struct ApiTestApp {
 
     new_project_name: String,
     new_group_name: String,
+    new_environment_name: String,
+    new_module_name: String,
+    // 导入弹窗中粘贴的文本
+    import_text: String,
+    // 导出代码的语言
+    snippet_lang: snippet::SnippetLang,
+    // 导入文件
+    import_path: String,
+    import_format: ImportFormat,
+    import_warnings: Vec<String>,
+    // 导出文件
+    export_path: String,
+    export_format: ExportFormat,
+    // 批量发送结果的导出路径, 对比基线及其路径
+    stats_path: String,
+    baseline_path: String,
+    baseline: Option<report::LoadReport>,
 
     // 当前项目
     project: Project,
@@ -196,6 +260,18 @@ impl Default for ApiTestApp {
                 .build()
                 .unwrap(),
             new_group_name: Default::default(),
+            new_environment_name: Default::default(),
+            new_module_name: Default::default(),
+            import_text: Default::default(),
+            snippet_lang: Default::default(),
+            import_path: Default::default(),
+            import_format: Default::default(),
+            import_warnings: Default::default(),
+            export_path: Default::default(),
+            export_format: Default::default(),
+            stats_path: Default::default(),
+            baseline_path: Default::default(),
+            baseline: Default::default(),
             new_project_name: Default::default(),
             action_status: Default::default(),
             saved: Default::default(),
@@ -213,6 +289,11 @@ impl Default for ApiTestApp {
                     g
                 }],
                 variables: vec![PairUi::from_kv("base", "http://127.00.1:3000")],
+                environments: Default::default(),
+                active_environment: None,
+                scripts: Default::default(),
+                modules: Default::default(),
+                dir: None,
             },
             is_pretty: true,
             remove_group: None,
@@ -338,16 +419,24 @@ impl ApiTestApp {
                                         match msg {
                                             WsMessage::Init(http_request_config, vec) => {}
                                             WsMessage::Send(http_request_config, vec) => {
-                                                let send_msg = if cfg.body_raw_type
+                                                let data = http_request_config.real_body_raw(&vec);
+                                                let send_msg = if http_request_config.body_raw_type
                                                     == RequestBodyRawType::Text
                                                 {
-                                                    let data = &cfg.body_raw;
                                                     tungstenite::Message::Text(data.into())
                                                 } else {
-                                                    let dat = util::read_binary(&cfg.body_raw)
-                                                        .await
-                                                        .unwrap();
-                                                    tungstenite::Message::Binary(dat.into())
+                                                    match util::read_binary(&data).await {
+                                                        Ok(dat) => tungstenite::Message::Binary(dat.into()),
+                                                        Err(err) => {
+                                                            ws_msgs_w.write().unwrap().push(
+                                                                Message::text(format!(
+                                                                    "> Send Error: {}",
+                                                                    err
+                                                                )),
+                                                            );
+                                                            continue;
+                                                        }
+                                                    }
                                                 };
                                                 match w.send(send_msg).await {
                                                     Ok(_) => {}
@@ -395,7 +484,10 @@ impl ApiTestApp {
     /// 保存当前正在操作的项目
     fn save_current_project(&mut self) {
         self.action_status = match util::save_project(SAVE_DIR, &self.project) {
-            Ok(_) => "save sucsess".to_owned(),
+            Ok(_) => {
+                self.project.dir = Some(SAVE_DIR.into());
+                "save sucsess".to_owned()
+            }
             Err(err) => err.to_string(),
         };
     }
@@ -493,6 +585,21 @@ impl ApiTestApp {
                             self.saved = saved;
                         }
                     }
+
+                    if ui.add(egui::Button::new("Import")).clicked() {
+                        self.modal.open = true;
+                        self.modal.title = "Import".to_owned();
+                        self.modal.r#type = ModalType::ImportFile;
+                        self.import_warnings.clear();
+                        ui.close_menu();
+                    }
+
+                    if ui.add(egui::Button::new("Export")).clicked() {
+                        self.modal.open = true;
+                        self.modal.title = "Export".to_owned();
+                        self.modal.r#type = ModalType::ExportFile;
+                        ui.close_menu();
+                    }
                 });
 
                 ui.menu_button("Setting", |ui| {
@@ -540,8 +647,27 @@ impl ApiTestApp {
                 ui.separator();
 
                 egui::ScrollArea::both().show(ui, |ui| {
+                    // 当前环境
+                    ui.horizontal(|ui| {
+                        ui.label("🌐");
+                        let selected = self
+                            .project
+                            .active_environment()
+                            .map(|e| e.name.to_owned())
+                            .unwrap_or_else(|| "无环境 (仅全局)".to_owned());
+                        egui::ComboBox::from_id_salt("active environment")
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.project.active_environment, None, "无环境 (仅全局)");
+                                for (i, env) in self.project.environments.iter().enumerate() {
+                                    ui.selectable_value(&mut self.project.active_environment, Some(i), &env.name);
+                                }
+                            });
+                    });
+                    ui.add_space(5.0);
+
                     let var_count = self.project.variables.len();
-                    CollapsingHeader::new(format!("Variables ({})", var_count))
+                    CollapsingHeader::new(format!("Globals ({})", var_count))
                         .default_open(false)
                         .show(ui, |ui| {
                             ui.horizontal(|ui| {
@@ -557,64 +683,121 @@ impl ApiTestApp {
 
                             ui.add_space(5.0);
 
-                            egui_extras::TableBuilder::new(ui)
-                                .striped(true)
-                                .resizable(true)
-                                .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
-                                .column(egui_extras::Column::auto())
-                                .column(egui_extras::Column::auto())
-                                .column(egui_extras::Column::auto().range(100.0..=400.0))
-                                .column(egui_extras::Column::auto())
-                                .min_scrolled_height(10.0)
-                                // .scroll_to_row(1, Some(egui::Align::BOTTOM))
-                                .header(20.0, |mut header| {
-                                    header.col(|ui| {
-                                        ui.label("启用").on_hover_text("勾选以启用该变量");
-                                    });
-                                    header.col(|ui| {
-                                        ui.label("Key");
-                                    });
-                                    header.col(|ui| {
-                                        ui.label("Value");
-                                    });
-                                    header.col(|ui| {
-                                        ui.label("");
+                            widget::variable_table(ui, "globals table", &mut self.project.variables);
+                        });
+
+                    CollapsingHeader::new(format!("Environments ({})", self.project.environments.len()))
+                        .default_open(false)
+                        .show(ui, |ui| {
+                            let input_add = ui.add(
+                                egui::TextEdit::singleline(&mut self.new_environment_name)
+                                    .hint_text("输入环境名并按回车添加...")
+                                    .desired_width(f32::INFINITY),
+                            );
+                            if input_add.lost_focus()
+                                && ui.input(|i| i.key_pressed(egui::Key::Enter))
+                                && !self.new_environment_name.is_empty()
+                            {
+                                let name = self.new_environment_name.to_owned();
+                                if self.project.environments.iter().any(|e| e.name == name) {
+                                    self.action_status = format!("环境 '{}' 已存在", name);
+                                } else {
+                                    self.project.environments.push(Environment::from_name(&name));
+                                    self.new_environment_name.clear();
+                                }
+                            }
+
+                            let mut remove_environment = None;
+                            for (env_index, env) in self.project.environments.iter_mut().enumerate() {
+                                let is_active = self.project.active_environment == Some(env_index);
+                                CollapsingHeader::new(format!(
+                                    "{}{} ({})",
+                                    if is_active { "✔ " } else { "" },
+                                    env.name,
+                                    env.variables.len()
+                                ))
+                                .id_salt(("environment", env_index))
+                                .default_open(false)
+                                .show(ui, |ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.add(egui::TextEdit::singleline(&mut env.name).desired_width(100.0));
+                                        if ui.button("➕ Add").clicked() {
+                                            env.variables.push(PairUi::default());
+                                        }
+                                        if error_button(ui, "Del").clicked() {
+                                            remove_environment = Some(env_index);
+                                        }
                                     });
-                                })
-                                .body(|mut body| {
-                                    self.project.variables.retain_mut(|el| {
-                                        let mut is_retain = true;
-                                        body.row(30.0, |mut row| {
-                                            row.col(|ui| {
-                                                let mut enabled = !el.disable;
-                                                if ui.checkbox(&mut enabled, "").changed() {
-                                                    el.disable = !enabled;
-                                                }
-                                            });
 
-                                            row.col(|ui| {
-                                                ui.add(
-                                                    egui::TextEdit::singleline(&mut el.key)
-                                                        .desired_width(f32::INFINITY),
-                                                );
-                                            });
+                                    ui.add_space(5.0);
 
-                                            row.col(|ui| {
-                                                ui.add(
-                                                    egui::TextEdit::singleline(&mut el.value)
-                                                        .desired_width(f32::INFINITY),
-                                                );
-                                            });
+                                    widget::variable_table(ui, ("environment table", env_index), &mut env.variables);
+                                });
+                            }
+                            if let Some(i) = remove_environment {
+                                self.project.remove_environment(i);
+                            }
+                        });
 
-                                            row.col(|ui| {
-                                                if error_button(ui, "Del").clicked() {
-                                                    is_retain = false;
-                                                }
-                                            });
+                    let has_scripts = !self.project.scripts.pre_request_script.trim().is_empty()
+                        || !self.project.scripts.post_response_script.trim().is_empty();
+                    CollapsingHeader::new(format!("Scripts{}", if has_scripts { " ✔" } else { "" }))
+                        .default_open(false)
+                        .show(ui, |ui| {
+                            ui.label(egui::RichText::new("项目中每个请求都执行, 先于组和请求自身的脚本").weak());
+                            widget::shared_scripts_editor(ui, "project scripts", &mut self.project.scripts);
+                        });
+
+                    CollapsingHeader::new(format!("Modules ({})", self.project.modules.len()))
+                        .default_open(false)
+                        .show(ui, |ui| {
+                            ui.label(egui::RichText::new("脚本中使用 import \"name\" as m; 导入, 也可以导入项目目录下的 name.rhai 文件").weak());
+                            let input_add = ui.add(
+                                egui::TextEdit::singleline(&mut self.new_module_name)
+                                    .hint_text("输入模块名并按回车添加...")
+                                    .desired_width(f32::INFINITY),
+                            );
+                            if input_add.lost_focus()
+                                && ui.input(|i| i.key_pressed(egui::Key::Enter))
+                                && !self.new_module_name.trim().is_empty()
+                            {
+                                let name = self.new_module_name.trim().to_owned();
+                                if self.project.modules.iter().any(|m| m.name == name) {
+                                    self.action_status = format!("模块 '{}' 已存在", name);
+                                } else {
+                                    self.project.modules.push(ScriptModule {
+                                        name,
+                                        source: Default::default(),
+                                    });
+                                    self.new_module_name.clear();
+                                }
+                            }
+
+                            let mut remove_module = None;
+                            for (module_index, module) in self.project.modules.iter_mut().enumerate() {
+                                CollapsingHeader::new(&module.name)
+                                    .id_salt(("module", module_index))
+                                    .default_open(false)
+                                    .show(ui, |ui| {
+                                        ui.horizontal(|ui| {
+                                            ui.add(egui::TextEdit::singleline(&mut module.name).desired_width(100.0));
+                                            if error_button(ui, "Del").clicked() {
+                                                remove_module = Some(module_index);
+                                            }
                                         });
-                                        is_retain
+                                        ui.add(
+                                            egui::TextEdit::multiline(&mut module.source)
+                                                .id_salt(("module source", module_index))
+                                                .font(egui::TextStyle::Monospace)
+                                                .code_editor()
+                                                .desired_rows(6)
+                                                .desired_width(f32::INFINITY),
+                                        );
                                     });
-                                });
+                            }
+                            if let Some(i) = remove_module {
+                                self.project.modules.remove(i);
+                            }
                         });
                     ui.add_space(5.0);
 
@@ -673,6 +856,12 @@ impl ApiTestApp {
                                                 self.select_test = Some((group_index, 0));
                                                 self.modal.r#type = ModalType::HandleGroup;
                                             }
+                                            if ui.button("📥").on_hover_text("从 cURL 导入").clicked() {
+                                                self.modal.open = true;
+                                                self.modal.title = "Import cURL".to_owned();
+                                                self.select_test = Some((group_index, 0));
+                                                self.modal.r#type = ModalType::ImportCurl;
+                                            }
                                         });
 
                                         ui.with_layout(
@@ -704,6 +893,16 @@ impl ApiTestApp {
                                                                 self.copy_test = Some((group_index, cfg_i));
                                                             }
 
+                                                            if ui.button("📤").on_hover_text("导出为 cURL / 代码").clicked() {
+                                                                self.modal.open = true;
+                                                                self.modal.title =
+                                                                    format!("Export {}", cfg.name);
+                                                                self.select_test =
+                                                                    Some((group_index, cfg_i));
+                                                                self.modal.r#type =
+                                                                    ModalType::ExportSnippet;
+                                                            }
+
                                                             if ui.button("✏️").on_hover_text("编辑测试").clicked() {
                                                                 self.modal.open = true;
                                                                 self.modal.title =
@@ -743,6 +942,7 @@ impl ApiTestApp {
                     let Some((i, ii)) = self.select_test else {
                         return;
                     };
+                    let variables = self.project.resolved_variables();
                     let Some(group) = self.project.groups.get_mut(i) else {
                         return;
                     };
@@ -759,7 +959,7 @@ impl ApiTestApp {
 
 
                     // 请求方式 - 第一行：输入控件
-                    let is_running = http_test.stats.sending > 0;
+                    let is_running = http_test.stats.running;
 
                     ui.horizontal(|ui| {
                         egui::ComboBox::from_id_salt("method")
@@ -774,28 +974,70 @@ impl ApiTestApp {
                                 }
                             });
 
+                        let controls_width = match (&http_test.request.method, &http_test.load_mode) {
+                            (Method::WS, _) => 70.0,
+                            (_, load::LoadMode::Count) => 300.0,
+                            (_, load::LoadMode::ConstantRate) => 300.0,
+                            (_, load::LoadMode::Duration) => 300.0,
+                            (_, load::LoadMode::Stages | load::LoadMode::Data) => 150.0,
+                        };
                         ui.add_sized(
-                            ui.available_size() - egui::vec2(
-                                if http_test.request.method != Method::WS { 150.0 } else { 70.0 },
-                                0.0
-                            ),
+                            ui.available_size() - egui::vec2(controls_width, 0.0),
                             egui::TextEdit::singleline(&mut http_test.request.url)
                                 .hint_text("url"),
                         );
 
                         if http_test.request.method != Method::WS {
-                            let count_input = ui.add(
-                                egui::TextEdit::singleline(&mut http_test.send_count_ui)
-                                    .desired_width(80.)
-                                    .hint_text("Count"),
-                            );
+                            egui::ComboBox::from_id_salt("load mode")
+                                .width(60.0)
+                                .selected_text(http_test.load_mode.as_ref())
+                                .show_ui(ui, |ui| {
+                                    for mode in &LOAD_MODES {
+                                        ui.selectable_value(&mut http_test.load_mode, mode.to_owned(), mode.as_ref());
+                                    }
+                                });
+
+                            match http_test.load_mode {
+                                load::LoadMode::Count => {
+                                    let count_input = ui.add(
+                                        egui::TextEdit::singleline(&mut http_test.send_count_ui)
+                                            .desired_width(80.)
+                                            .hint_text("Count"),
+                                    );
 
-                            if let Ok(count) = http_test.send_count_ui.parse::<usize>() {
-                                if count > 10_000_000 {
-                                    count_input.on_hover_text("警告: 超过1000万可能导致性能问题");
-                                } else if count > 100_000 {
-                                    count_input.on_hover_text("提示: 超过10万可能需要较长时间");
+                                    if let Ok(count) = http_test.send_count_ui.parse::<usize>() {
+                                        if count > 10_000_000 {
+                                            count_input.on_hover_text("警告: 超过1000万可能导致性能问题");
+                                        } else if count > 100_000 {
+                                            count_input.on_hover_text("提示: 超过10万可能需要较长时间");
+                                        }
+                                    }
+                                    widget::concurrency_input(ui, &mut http_test.concurrency_ui);
                                 }
+                                load::LoadMode::ConstantRate => {
+                                    ui.add(
+                                        egui::TextEdit::singleline(&mut http_test.rate_ui)
+                                            .desired_width(60.)
+                                            .hint_text("RPS"),
+                                    )
+                                    .on_hover_text("每秒请求数, 按固定时间表发送, 延迟从计划发送时间计算");
+                                    ui.add(
+                                        egui::TextEdit::singleline(&mut http_test.duration_ui)
+                                            .desired_width(60.)
+                                            .hint_text("秒"),
+                                    )
+                                    .on_hover_text("持续时长 (秒)");
+                                }
+                                load::LoadMode::Duration => {
+                                    widget::concurrency_input(ui, &mut http_test.concurrency_ui);
+                                    ui.add(
+                                        egui::TextEdit::singleline(&mut http_test.duration_ui)
+                                            .desired_width(60.)
+                                            .hint_text("秒"),
+                                    )
+                                    .on_hover_text("持续时长 (秒)");
+                                }
+                                load::LoadMode::Stages | load::LoadMode::Data => {}
                             }
                         }
 
@@ -809,71 +1051,119 @@ impl ApiTestApp {
                             if http_test.request.method == Method::WS {
                                 if let Some(ws_tx) = &self.ws_tx {
                                     let cfg = http_test.request.to_owned();
-                                    let variables = self.project.variables.to_owned();
+                                    let variables = variables.to_owned();
                                     let tx = ws_tx.clone();
                                     self.rt.spawn(async move {
                                         tx.send(WsMessage::Send(cfg, variables)).await;
                                     });
                                 }
                             } else {
-                                http_test.send_before_init();
-                                if http_test.send_count <= 0 {
+                                let plan = match http_test.load_plan(self.project.dir.as_deref()) {
+                                    Ok(plan) => plan,
+                                    Err(err) => {
+                                        self.action_status = format!("{:#}", err);
+                                        return;
+                                    }
+                                };
+                                if plan.is_empty() {
+                                    self.action_status = "批量发送设置为空, 请检查发送次数 / 速率 / 时长".to_owned();
                                     return;
                                 }
+                                let id = http_test.send_before_init(&plan);
 
                                 http_test.stats.pending = 0;
                                 http_test.stats.sending = http_test.send_count;
 
                                 let cfg = Arc::new(http_test.request.to_owned());
-                                let variables = Arc::new(self.project.variables.to_owned());
+                                let variables = Arc::new(variables.to_owned());
+                                let assertions = Arc::new(http_test.assertions.to_owned());
+                                let library = ScriptLibrary {
+                                    modules: self.project.modules.to_owned(),
+                                    base_dir: self.project.dir.to_owned(),
+                                };
+                                let scripts = Arc::new(CompiledScripts::compile_with(
+                                    &library,
+                                    &[
+                                        (ScriptScope::Project, &self.project.scripts),
+                                        (ScriptScope::Group, &group.scripts),
+                                    ],
+                                    &http_test.request,
+                                ));
                                 let tx = self.http_tx.clone();
                                 let ctx_clone = ctx.clone();
-                                let send_count = http_test.send_count;
 
-                                self.rt.spawn(async move {
-                                    Self::send_http_batch(cfg, variables, tx, ctx_clone, send_count).await;
+                                let handle = self.rt.spawn(async move {
+                                    Self::send_http_batch(id, cfg, variables, assertions, scripts, tx, ctx_clone, plan).await;
                                 });
+                                http_test.batch_abort = Some(handle.abort_handle());
                             }
                         }
 
                         if is_running {
                             if ui.button("Cancel").clicked() {
-                                http_test.stats.sending = 0;
-                                http_test.stats.total_end_time = Some(std::time::Instant::now());
+                                // 中止批量发送任务, 在途请求随之被丢弃
+                                if let Some(handle) = http_test.batch_abort.take() {
+                                    handle.abort();
+                                }
+                                http_test.finish_run();
                             }
                         }
                     });
 
                     // 第二行：统计信息和进度条
                     if http_test.request.method != Method::WS {
+                        if http_test.load_mode == load::LoadMode::Stages {
+                            CollapsingHeader::new("Stages").default_open(true).show(ui, |ui| {
+                                ui.add_enabled_ui(!is_running, |ui| {
+                                    widget::load_stages_table(ui, "load stages", &mut http_test.load_profile);
+                                });
+                            });
+                        }
+
                         let stats = &http_test.stats;
-                        let total = stats.total_requests() + stats.sending;
+                        let completed = stats.total_requests();
 
-                        if total > 0 {
+                        if let Some(progress) = stats.progress().filter(|_| completed > 0 || stats.running) {
                             ui.horizontal(|ui| {
-                                let completed = stats.total_requests();
-                                let progress = completed as f32 / total as f32;
-
                                 ui.add(
                                     egui::ProgressBar::new(progress)
                                         .desired_width(200.0)
                                         .show_percentage()
                                 );
 
-                                ui.label(format!(
-                                    "完成: {} / {} ({:.1}%)",
-                                    completed.to_formatted_string(&Locale::en),
-                                    total.to_formatted_string(&Locale::en),
-                                    progress * 100.0
-                                ));
+                                match (stats.planned_requests, stats.planned_duration) {
+                                    (Some(total), _) => ui.label(format!(
+                                        "完成: {} / {} ({:.1}%)",
+                                        completed.to_formatted_string(&Locale::en),
+                                        total.to_formatted_string(&Locale::en),
+                                        progress * 100.0
+                                    )),
+                                    (None, planned) => ui.label(format!(
+                                        "完成: {}  用时: {:.0}s / {:.0}s",
+                                        completed.to_formatted_string(&Locale::en),
+                                        stats.total_duration().or(stats.current_duration()).unwrap_or(0.0),
+                                        planned.unwrap_or(0.0)
+                                    )),
+                                };
+
+                                if let Some(stage) = stats.active_stage() {
+                                    ui.separator();
+                                    ui.label(format!("阶段: {} / {}", stage + 1, stats.stage_stats.len()));
+                                }
 
                                 ui.separator();
                                 ui.label(format!(
                                     "成功:{} 失败:{}",
                                     stats.success, stats.failed
                                 ));
+                                if stats.assertion_failed > 0 {
+                                    ui.colored_label(
+                                        egui::Color32::RED,
+                                        format!("断言失败:{}", stats.assertion_failed),
+                                    );
+                                }
 
-                                if stats.sending > 0 {
+                                if stats.running {
                                     ui.separator();
                                     if let Some(qps) = stats.realtime_qps() {
                                         ui.label(format!("实时QPS: {:.0}", qps));
@@ -937,6 +1227,16 @@ impl ApiTestApp {
                                                         raw_type.as_ref(),
                                                     );
                                                 });
+                                                if http_test.request.body_raw_type
+                                                    != RequestBodyRawType::BinaryFile
+                                                {
+                                                    ui.separator();
+                                                    ui.checkbox(
+                                                        &mut http_test.request.body_raw_literal,
+                                                        "原样发送",
+                                                    )
+                                                    .on_hover_text("不替换 body 中的 {{var}}");
+                                                }
                                             });
                                         });
 
@@ -1035,6 +1335,11 @@ impl ApiTestApp {
                                     ui.monospace("  base64_encode(), base64_decode()");
                                     ui.monospace("  timestamp(), uuid(), random_string(len)");
 
+                                    ui.add_space(5.0);
+                                    ui.label("测试:");
+                                    ui.monospace("  test(name, || { expect(v).to_equal(x); })");
+                                    ui.monospace("  to_equal(), to_contain(), to_be_below(), to_match()");
+
                                     ui.add_space(5.0);
                                     ui.label("示例 - 判断业务状态码:");
                                     ui.code("let result = parse_json(response.body);");
@@ -1042,6 +1347,31 @@ impl ApiTestApp {
                                 });
                             });
                         }
+                        RequestTab::Assertions => {
+                            widget::assertion_table(ui, "assertion scroll", &mut http_test.assertions);
+                        }
+                        RequestTab::Data => {
+                            ui.horizontal(|ui| {
+                                ui.label("File");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut http_test.data_file)
+                                        .hint_text("./users.csv 或 ./users.json")
+                                        .desired_width(400.0),
+                                );
+                                if ui
+                                    .add_enabled(!http_test.data_file.trim().is_empty(), egui::Button::new("Preview"))
+                                    .clicked()
+                                {
+                                    match data_file::load_data_file(http_test.data_file.trim(), self.project.dir.as_deref()) {
+                                        Ok(rows) => http_test.data_rows = Arc::new(rows),
+                                        Err(err) => self.action_status = format!("{:#}", err),
+                                    }
+                                }
+                            });
+                            ui.label("每行的列作为变量 ({{列名}}), Data 模式下每行发送一次, 其他模式按发送顺序循环使用; 相对路径相对于项目文件所在目录");
+                            ui.separator();
+                            widget::data_rows_table(ui, &http_test.data_rows);
+                        }
                     };
 
                     ui.separator();
@@ -1093,6 +1423,12 @@ impl ApiTestApp {
 
                     // 请求结果
                     let Some(ref response) = http_test.response else {
+                        // 没有任何响应时显示错误分布
+                        if !http_test.stats.errors.is_empty() {
+                            ui.heading("请求失败");
+                            ui.separator();
+                            widget::breakdown_tables(ui, &http_test.stats);
+                        }
                         return;
                     };
                     // 从字节码中初始化数据
@@ -1112,14 +1448,14 @@ impl ApiTestApp {
                                 egui::TextEdit::singleline(&mut http_test.download_path)
                                     .hint_text(r#"c:/out.(jpg|txt)"#),
                             );
-                            if http_test.response_tab_ui != ResponseTab::Stats {
+                            if matches!(http_test.response_tab_ui, ResponseTab::Data | ResponseTab::Header) {
                                 if ui
                                     .add_enabled(
                                         !http_test.download_path.is_empty(),
                                         egui::Button::new(match http_test.response_tab_ui {
                                             ResponseTab::Data => "Download Data",
                                             ResponseTab::Header => "Download Header",
-                                            ResponseTab::Stats => "",
+                                            _ => "",
                                         }),
                                     )
                                     .clicked()
@@ -1130,7 +1466,7 @@ impl ApiTestApp {
                                         match http_test.response_tab_ui {
                                             ResponseTab::Data => data_vec,
                                             ResponseTab::Header => response.headers_str.as_bytes(),
-                                            ResponseTab::Stats => &[],
+                                            _ => &[],
                                         },
                                     ) {
                                         Ok(_) => {
@@ -1206,6 +1542,57 @@ impl ApiTestApp {
                                 egui::ScrollArea::vertical()
                                     .auto_shrink([false, false])
                                     .show(ui, |ui| {
+                                        ui.horizontal(|ui| {
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut self.stats_path)
+                                                    .hint_text("./result.json 或 ./result.csv")
+                                                    .desired_width(300.0),
+                                            );
+                                            let has_path = !self.stats_path.trim().is_empty();
+                                            if ui
+                                                .add_enabled(has_path && !stats.running, egui::Button::new("Export"))
+                                                .on_hover_text("按扩展名导出为 CSV 或 JSON")
+                                                .clicked()
+                                            {
+                                                let path = self.stats_path.trim();
+                                                self.action_status = match report::LoadReport::from_stats(&http_test.name, stats).save(path) {
+                                                    Ok(_) => format!("已导出到 {}", path),
+                                                    Err(err) => format!("{:#}", err),
+                                                };
+                                            }
+                                        });
+                                        ui.horizontal(|ui| {
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut self.baseline_path)
+                                                    .hint_text("./baseline.json")
+                                                    .desired_width(300.0),
+                                            );
+                                            if ui
+                                                .add_enabled(!self.baseline_path.trim().is_empty(), egui::Button::new("Load Baseline"))
+                                                .on_hover_text("读取之前导出的 JSON 结果作为对比基线")
+                                                .clicked()
+                                            {
+                                                match report::LoadReport::load(self.baseline_path.trim()) {
+                                                    Ok(baseline) => self.baseline = Some(baseline),
+                                                    Err(err) => self.action_status = format!("{:#}", err),
+                                                }
+                                            }
+                                            if self.baseline.is_some() && ui.button("Clear Baseline").clicked() {
+                                                self.baseline = None;
+                                            }
+                                        });
+
+                                        if let Some(baseline) = &self.baseline {
+                                            ui.group(|ui| {
+                                                ui.heading(format!("📐 基线对比 ({} {})", baseline.test, baseline.created_at));
+                                                ui.separator();
+                                                let current = report::LoadReport::from_stats(&http_test.name, stats);
+                                                let diffs = report::compare(baseline, &current, &report::Thresholds::default());
+                                                widget::baseline_table(ui, &diffs);
+                                            });
+                                            ui.separator();
+                                        }
+
                                         ui.columns(2, |columns| {
                                             // 左列：请求统计
                                             columns[0].group(|ui| {
@@ -1227,6 +1614,11 @@ impl ApiTestApp {
                                                     ui.colored_label(egui::Color32::RED, format!("{}", stats.failed.to_formatted_string(&Locale::en)));
                                                 });
 
+                                                ui.horizontal(|ui| {
+                                                    ui.label("断言失败:");
+                                                    ui.colored_label(egui::Color32::RED, format!("{}", stats.assertion_failed.to_formatted_string(&Locale::en)));
+                                                });
+
                                                 ui.add_space(5.0);
 
                                                 // 成功率进度条
@@ -1249,7 +1641,7 @@ impl ApiTestApp {
                                                 if let Some(min) = stats.min_response_time() {
                                                     ui.horizontal(|ui| {
                                                         ui.label("最小 (Min):");
-                                                        ui.strong(format!("{} ms", min));
+                                                        ui.strong(format!("{:.2} ms", min));
                                                     });
                                                 }
 
@@ -1263,7 +1655,7 @@ impl ApiTestApp {
                                                 if let Some(max) = stats.max_response_time() {
                                                     ui.horizontal(|ui| {
                                                         ui.label("最大 (Max):");
-                                                        ui.strong(format!("{} ms", max));
+                                                        ui.strong(format!("{:.2} ms", max));
                                                     });
                                                 }
 
@@ -1273,21 +1665,21 @@ impl ApiTestApp {
                                                 if let Some(p50) = stats.percentile(50.0) {
                                                     ui.horizontal(|ui| {
                                                         ui.label("  P50:");
-                                                        ui.label(format!("{} ms", p50));
+                                                        ui.label(format!("{:.2} ms", p50));
                                                     });
                                                 }
 
                                                 if let Some(p95) = stats.percentile(95.0) {
                                                     ui.horizontal(|ui| {
                                                         ui.label("  P95:");
-                                                        ui.label(format!("{} ms", p95));
+                                                        ui.label(format!("{:.2} ms", p95));
                                                     });
                                                 }
 
                                                 if let Some(p99) = stats.percentile(99.0) {
                                                     ui.horizontal(|ui| {
                                                         ui.label("  P99:");
-                                                        ui.label(format!("{} ms", p99));
+                                                        ui.label(format!("{:.2} ms", p99));
                                                     });
                                                 }
                                             });
@@ -1314,6 +1706,19 @@ impl ApiTestApp {
                                                         ui.colored_label(egui::Color32::from_rgb(0, 150, 255), format!("{:.0}", qps));
                                                     });
                                                 }
+
+                                                if let Some(script) = stats.avg_script_time() {
+                                                    ui.horizontal(|ui| {
+                                                        ui.label("脚本耗时 (Avg):");
+                                                        ui.strong(format!("{:.3} ms", script));
+                                                    })
+                                                    .response
+                                                    .on_hover_text(format!(
+                                                        "{} 次, 共 {:.3} s",
+                                                        stats.script_runs,
+                                                        stats.script_time.as_secs_f64()
+                                                    ));
+                                                }
                                             });
 
                                             columns[1].group(|ui| {
@@ -1347,11 +1752,83 @@ impl ApiTestApp {
                                                 }
                                             });
                                         });
+
+                                        if !stats.status_codes.is_empty() || !stats.errors.is_empty() {
+                                            ui.separator();
+                                            ui.group(|ui| {
+                                                ui.heading("🧾 状态码和错误");
+                                                ui.separator();
+                                                widget::breakdown_tables(ui, stats);
+                                            });
+                                        }
+
+                                        if !stats.timeline.is_empty() {
+                                            ui.separator();
+                                            ui.group(|ui| {
+                                                ui.heading("📈 每秒统计");
+                                                ui.separator();
+                                                widget::timeline_charts(ui, &stats.timeline, stats.running);
+                                            });
+                                        }
+
+                                        if !stats.stage_stats.is_empty() {
+                                            ui.separator();
+                                            ui.group(|ui| {
+                                                ui.heading("🪜 阶段统计");
+                                                ui.separator();
+                                                widget::stage_stats_table(
+                                                    ui,
+                                                    &stats.stage_stats,
+                                                    &http_test.load_profile,
+                                                    stats.active_stage(),
+                                                );
+                                            });
+                                        }
                                     });
                             } else {
                                 ui.label("暂无统计数据");
                             }
                         }
+                        ResponseTab::Assertions => {
+                            if response.assertion_results.is_empty() && response.script_tests.is_empty() {
+                                ui.label("未配置断言");
+                            } else {
+                                egui::ScrollArea::vertical()
+                                    .id_salt("response assertion scroll")
+                                    .auto_shrink([false, false])
+                                    .show(ui, |ui| {
+                                        if !response.assertion_results.is_empty() {
+                                            ui.strong("断言");
+                                            widget::assertion_results(ui, &response.assertion_results);
+                                            ui.add_space(10.0);
+                                        }
+                                        if !response.script_tests.is_empty() {
+                                            ui.strong("脚本测试 test()");
+                                            widget::assertion_results(ui, &response.script_tests);
+                                        }
+                                    });
+                            }
+                        }
+                        ResponseTab::Iterations => {
+                            if http_test.iterations.is_empty() {
+                                ui.label("使用 Data 模式发送后显示每行数据的结果");
+                            } else {
+                                widget::iteration_table(ui, &http_test.iterations);
+                            }
+                        }
+                        ResponseTab::Console => {
+                            egui::ScrollArea::both()
+                                .id_salt("response console scroll")
+                                .auto_shrink([false, false])
+                                .show(ui, |ui| {
+                                    widget::console_output(
+                                        ui,
+                                        &response.console,
+                                        response.pre_script_time,
+                                        response.post_script_time,
+                                    );
+                                });
+                        }
                     }
                 });
         });
@@ -1364,6 +1841,7 @@ impl ApiTestApp {
                 ctx.request_repaint();
             }
 
+            let mut close_modal = false;
             egui::Window::new(&self.modal.title)
                 .id(egui::Id::new("Window Model")) // required since we change the title
                 // .open(&mut self.modal.open)
@@ -1393,6 +1871,13 @@ impl ApiTestApp {
                                 self.remove_group = Some(*i);
                             }
                             ui.separator();
+                            ui.collapsing("Scripts", |ui| {
+                                ui.label(
+                                    egui::RichText::new("组内每个请求都执行, 在项目脚本之后、请求自身的脚本之前").weak(),
+                                );
+                                widget::shared_scripts_editor(ui, ("group scripts", *i), &mut group.scripts);
+                            });
+                            ui.separator();
                             let input_add = ui.add(
                                 egui::TextEdit::singleline(&mut group.new_child_name)
                                     .hint_text("Enter Add Test"),
@@ -1428,6 +1913,143 @@ impl ApiTestApp {
                             }
                         });
                     }
+                    ModalType::ImportCurl => {
+                        let Some((i, _)) = self.select_test else {
+                            return;
+                        };
+                        let Some(group) = self.project.groups.get_mut(i) else {
+                            return;
+                        };
+                        ui.vertical(|ui| {
+                            ui.label(format!("导入到组: {}", group.name));
+                            ui.add(
+                                egui::TextEdit::multiline(&mut self.import_text)
+                                    .font(egui::TextStyle::Monospace)
+                                    .hint_text("curl 'https://api.example.com' -H 'Accept: application/json'")
+                                    .desired_rows(8)
+                                    .desired_width(500.0),
+                            );
+                            if ui
+                                .add_enabled(!self.import_text.trim().is_empty(), egui::Button::new("Import"))
+                                .clicked()
+                            {
+                                match curl::import_curl(&self.import_text) {
+                                    Ok(test) => {
+                                        self.action_status = format!("已导入 {}", test.name);
+                                        group.childrent.push(test);
+                                        self.select_test = Some((i, group.childrent.len() - 1));
+                                        self.import_text.clear();
+                                        close_modal = true;
+                                    }
+                                    Err(err) => {
+                                        self.action_status = err.to_string();
+                                    }
+                                }
+                            }
+                        });
+                    }
+                    ModalType::ExportSnippet => {
+                        let Some((i, ii)) = self.select_test else {
+                            return;
+                        };
+                        let variables = self.project.resolved_variables();
+                        let Some(http_test) = self.project.groups.get(i).and_then(|g| g.childrent.get(ii)) else {
+                            return;
+                        };
+                        widget::horizontal_tabs(ui, SNIPPET_LANGS.iter(), &mut self.snippet_lang);
+                        ui.separator();
+                        match snippet::generate(&http_test.request, &variables, &self.snippet_lang) {
+                            Ok(code) => {
+                                if ui.button("📋 Copy").clicked() {
+                                    ui.ctx().copy_text(code.to_owned());
+                                    self.action_status = "已复制到剪贴板".to_owned();
+                                }
+                                egui::ScrollArea::both()
+                                    .id_salt("snippet scroll")
+                                    .max_height(400.0)
+                                    .show(ui, |ui| {
+                                        widget::code_view_ui(ui, &code);
+                                    });
+                            }
+                            Err(err) => {
+                                widget::error_label(ui, err.to_string());
+                            }
+                        }
+                    }
+                    ModalType::ImportFile => {
+                        ui.vertical(|ui| {
+                            widget::horizontal_tabs(ui, IMPORT_FORMATS.iter(), &mut self.import_format);
+                            ui.horizontal(|ui| {
+                                ui.label("File");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.import_path)
+                                        .hint_text("./collection.json")
+                                        .desired_width(400.0),
+                                );
+                            });
+                            if ui
+                                .add_enabled(!self.import_path.trim().is_empty(), egui::Button::new("Import"))
+                                .clicked()
+                            {
+                                let result = self.import_format.import_file(self.import_path.trim());
+                                match result {
+                                    Ok(result) => {
+                                        self.action_status = format!(
+                                            "已导入 {} 个组, {} 个环境",
+                                            result.groups.len(),
+                                            result.environments.len()
+                                        );
+                                        self.import_warnings = result.warnings.clone();
+                                        self.project.merge_import(result);
+                                        if self.import_warnings.is_empty() {
+                                            close_modal = true;
+                                        }
+                                    }
+                                    Err(err) => {
+                                        self.action_status = err.to_string();
+                                    }
+                                }
+                            }
+                            if !self.import_warnings.is_empty() {
+                                ui.separator();
+                                ui.label(format!("以下内容需要手动处理 ({})", self.import_warnings.len()));
+                                for warning in &self.import_warnings {
+                                    ui.label(format!("⚠ {}", warning));
+                                }
+                            }
+                        });
+                    }
+                    ModalType::ExportFile => {
+                        ui.vertical(|ui| {
+                            widget::horizontal_tabs(ui, EXPORT_FORMATS.iter(), &mut self.export_format);
+                            ui.horizontal(|ui| {
+                                ui.label("File");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.export_path)
+                                        .hint_text(match self.export_format {
+                                            ExportFormat::Har => "./requests.har",
+                                            ExportFormat::HttpFile => "./http/",
+                                        })
+                                        .desired_width(400.0),
+                                );
+                            });
+                            if ui
+                                .add_enabled(!self.export_path.trim().is_empty(), egui::Button::new("Export"))
+                                .clicked()
+                            {
+                                let result = self.export_format.export_to(&self.project, self.export_path.trim());
+                                match result {
+                                    Ok(_) => {
+                                        self.action_status = format!("已导出到 {}", self.export_path.trim());
+                                        close_modal = true;
+                                    }
+                                    Err(err) => {
+                                        self.action_status = err.to_string();
+                                    }
+                                }
+                            }
+                        });
+                    }
                     ModalType::LoadProject => {
                         ui.vertical(|ui| {
                             for i in 0..self.saved.len() {
@@ -1450,38 +2072,26 @@ impl ApiTestApp {
                         });
                     }
                 });
+            if close_modal {
+                self.modal.open = false;
+            }
         }
     }
 }
 
 impl ApiTestApp {
+    #[allow(clippy::too_many_arguments)]
     async fn send_http_batch(
+        id: load::RunId,
         cfg: Arc<HttpRequestConfig>,
         variables: Arc<Vec<PairUi>>,
-        tx: tokio::sync::mpsc::Sender<Result<HttpResponse>>,
+        assertions: Arc<Vec<assertion::Assertion>>,
+        scripts: Arc<CompiledScripts>,
+        tx: tokio::sync::mpsc::Sender<load::LoadEvent>,
         ctx_clone: egui::Context,
-        send_count: usize
+        plan: load::LoadPlan,
     ) {
-        let max_concurrent = 10000;
-        let mut futures = FuturesUnordered::new();
-        let mut sent = 0;
-
-        while sent < send_count || !futures.is_empty() {
-            while sent < send_count && futures.len() < max_concurrent {
-                let req_cfg = cfg.clone();
-                let vars = variables.clone();
-                let tx = tx.clone();
-
-                futures.push(async move {
-                    let result = util::http_send(&*req_cfg, &*vars).await;
-                    let _ = tx.send(result).await;
-                });
-                sent += 1;
-            }
-
-            if futures.next().await.is_some() {
-            }
-        }
+        load::run(id, cfg, variables, assertions, scripts, plan, tx).await;
         ctx_clone.request_repaint();
     }
 
@@ -1515,12 +2125,17 @@ impl ApiTestApp {
         let mut processed = 0;
 
         while processed < MAX_PROCESS_PER_FRAME {
-            let result = match self.http_rx.try_recv() {
-                Ok(result) => result,
+            let event = match self.http_rx.try_recv() {
+                Ok(event) => event,
                 Err(_) => break,
             };
 
-            self.handle_http_response(result);
+            match event {
+                load::LoadEvent::Response { id, stage, row, completed_at, result } => {
+                    self.handle_http_response(id, stage, row, completed_at, result)
+                }
+                load::LoadEvent::Finished { id } => self.handle_batch_finished(id),
+            }
             processed += 1;
         }
 
@@ -1529,55 +2144,52 @@ impl ApiTestApp {
         }
     }
 
-    fn handle_http_response(&mut self, result: Result<HttpResponse>) {
-        let Some((group_idx, test_idx)) = self.select_test else {
-            return;
-        };
+    fn selected_test_mut(&mut self) -> Option<&mut HttpTest> {
+        let (group_idx, test_idx) = self.select_test?;
+        self.project.groups.get_mut(group_idx)?.childrent.get_mut(test_idx)
+    }
 
-        let Some(group) = self.project.groups.get_mut(group_idx) else {
-            return;
-        };
+    /// 按 id 查找测试, 批量发送期间切换选中的测试也不会把结果记到别的测试上
+    fn test_by_id_mut(&mut self, id: u64) -> Option<&mut HttpTest> {
+        self.project
+            .groups
+            .iter_mut()
+            .flat_map(|group| group.childrent.iter_mut())
+            .find(|test| test.id == id)
+    }
 
-        let Some(http_test) = group.childrent.get_mut(test_idx) else {
+    fn handle_http_response(
+        &mut self,
+        id: load::RunId,
+        stage: usize,
+        row: Option<usize>,
+        completed_at: std::time::Instant,
+        result: Result<HttpResponse>,
+    ) {
+        // 已取消或已被新一次发送替换的运行, 其剩余事件直接丢弃
+        let Some(http_test) = self.test_by_id_mut(id.test).filter(|test| test.is_current_run(id)) else {
             return;
         };
 
-        match result {
+        http_test.record_result(stage, row, &result, completed_at);
+        let modified_vars = match result {
             Ok(response) => {
-                http_test.stats.add_response_time(response.duration);
-                http_test.stats.total_upload_bytes += response.request_size;
-                http_test.stats.total_download_bytes += response.response_size;
-
-                let is_success = response.status.is_success();
-
-                // 应用脚本修改的变量到项目
-                if let Some(modified_vars) = &response.modified_vars {
-                    for var in modified_vars {
-                        if let Some(existing) = self.project.variables.iter_mut().find(|v| v.key == var.key) {
-                            existing.value = var.value.clone();
-                        } else {
-                            self.project.variables.push(var.clone());
-                        }
-                    }
-                }
-
+                let modified_vars = response.modified_vars.clone();
                 http_test.response = Some(response);
-                http_test.stats.sending -= 1;
-
-                if is_success {
-                    http_test.stats.success += 1;
-                } else {
-                    http_test.stats.failed += 1;
-                }
-            }
-            Err(_) => {
-                http_test.stats.sending -= 1;
-                http_test.stats.failed += 1;
+                modified_vars
             }
+            Err(_) => None,
+        };
+
+        // 应用脚本修改的变量到项目
+        if let Some(modified_vars) = modified_vars {
+            self.project.apply_modified_vars(&modified_vars);
         }
+    }
 
-        if http_test.stats.sending == 0 {
-            http_test.stats.total_end_time = Some(std::time::Instant::now());
+    fn handle_batch_finished(&mut self, id: load::RunId) {
+        if let Some(http_test) = self.test_by_id_mut(id.test).filter(|test| test.is_current_run(id)) {
+            http_test.finish_run();
         }
     }
 
@@ -1630,6 +2242,10 @@ pub enum ModalType {
     HandleGroup,
     HandleTest,
     LoadProject,
+    ImportCurl,
+    ExportSnippet,
+    ImportFile,
+    ExportFile,
 }
 
 #[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
@@ -0,0 +1,392 @@
+//! 从 OpenAPI 3 / Swagger 2 文档 (JSON 或 YAML) 生成项目
+
+use anyhow::{bail, Result};
+use serde_json::{Map, Value};
+
+use crate::{
+    Environment, Group, HttpRequestConfig, HttpTest, ImportResult, Method, PairUi, RequestBodyRawType,
+    RequestBodyTab,
+};
+
+const METHODS: [&str; 8] = ["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// 每个 tag 对应一个 Group, 每个操作对应一个 HttpTest
+///
+/// 服务地址 `base` 和路径参数示例保存到以文档标题命名的环境中, 不会被项目中已有的同名全局变量遮住
+pub fn import_openapi(data: &str) -> Result<ImportResult> {
+    let doc: Value = match serde_json::from_str(data) {
+        Ok(doc) => doc,
+        Err(_) => serde_json::to_value(serde_yaml::from_str::<serde_yaml::Value>(data)?)?,
+    };
+
+    let swagger2 = doc["swagger"].as_str().is_some_and(|v| v.starts_with('2'));
+    if !swagger2 && !doc["openapi"].as_str().is_some_and(|v| v.starts_with('3')) {
+        bail!("不是 OpenAPI 3 / Swagger 2 文档");
+    }
+    let Some(paths) = doc["paths"].as_object() else {
+        bail!("文档中没有 paths");
+    };
+
+    let mut result = ImportResult::default();
+    let base = base_url(&doc, swagger2, &mut result.warnings);
+    result.variables.push(PairUi::from_kv("base", &base));
+
+    for (path, item) in paths {
+        let item = resolve(&doc, item);
+        for method in METHODS {
+            let Some(operation) = item.get(method) else {
+                continue;
+            };
+            let test = convert_operation(&doc, swagger2, path, method, item, operation, &mut result);
+
+            let tag = operation["tags"][0].as_str().unwrap_or("default");
+            match result.groups.iter_mut().find(|g| g.name == tag) {
+                Some(group) => group.childrent.push(test),
+                None => {
+                    let mut group = Group::from_name(tag.to_owned());
+                    group.childrent.push(test);
+                    result.groups.push(group);
+                }
+            }
+        }
+    }
+
+    let name = doc["info"]["title"].as_str().filter(|t| !t.trim().is_empty()).unwrap_or("OpenAPI");
+    result.environments.push(Environment {
+        name: name.trim().to_owned(),
+        variables: std::mem::take(&mut result.variables),
+    });
+    Ok(result)
+}
+
+fn base_url(doc: &Value, swagger2: bool, warnings: &mut Vec<String>) -> String {
+    if swagger2 {
+        let scheme = doc["schemes"][0].as_str().unwrap_or("https");
+        let host = doc["host"].as_str().unwrap_or("localhost");
+        let base_path = doc["basePath"].as_str().unwrap_or("");
+        return format!("{}://{}{}", scheme, host, base_path.trim_end_matches('/'));
+    }
+
+    let server = &doc["servers"][0];
+    let mut url = server["url"].as_str().unwrap_or("").to_owned();
+    // 服务器变量使用默认值
+    if let Some(vars) = server["variables"].as_object() {
+        for (name, var) in vars {
+            if let Some(default) = var["default"].as_str() {
+                url = url.replace(&format!("{{{}}}", name), default);
+            }
+        }
+    }
+    // 省略 servers 或使用相对地址时相对于文档所在的服务器, 导入时无从得知, 使用 localhost
+    if !url.contains("://") {
+        let path = url.trim_start_matches('/').trim_end_matches('/');
+        let absolute = match path {
+            "" => "http://localhost".to_owned(),
+            path => format!("http://localhost/{}", path),
+        };
+        warnings.push(format!(
+            "servers 地址 \"{}\" 是相对地址, base 变量已设为 {}, 请在环境中修改",
+            url, absolute
+        ));
+        return absolute;
+    }
+    url.trim_end_matches('/').to_owned()
+}
+
+/// Swagger 2 操作的请求格式, 未指定时使用文档级的 consumes
+fn consumes<'a>(doc: &'a Value, operation: &'a Value) -> Vec<&'a str> {
+    let list = match operation["consumes"].as_array() {
+        Some(list) => list,
+        None => doc["consumes"].as_array().map(|v| v.as_slice()).unwrap_or_default(),
+    };
+    list.iter().filter_map(|v| v.as_str()).collect()
+}
+
+fn convert_operation(
+    doc: &Value,
+    swagger2: bool,
+    path: &str,
+    method: &str,
+    item: &Value,
+    operation: &Value,
+    result: &mut ImportResult,
+) -> HttpTest {
+    let name = operation["summary"]
+        .as_str()
+        .or(operation["operationId"].as_str())
+        .map(|s| s.to_owned())
+        .unwrap_or_else(|| format!("{} {}", method.to_uppercase(), path));
+
+    let mut cfg = HttpRequestConfig {
+        method: Method::from_name(method).unwrap_or_default(),
+        // {id} -> {{id}}
+        url: format!("{{{{base}}}}{}", path.replace('{', "{{").replace('}', "}}")),
+        ..Default::default()
+    };
+
+    // 操作级参数覆盖路径级同名参数
+    let mut params: Vec<&Value> = Vec::new();
+    for param in operation["parameters"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .chain(item["parameters"].as_array().into_iter().flatten())
+    {
+        let param = resolve(doc, param);
+        if !params
+            .iter()
+            .any(|p| p["name"] == param["name"] && p["in"] == param["in"])
+        {
+            params.push(param);
+        }
+    }
+
+    let mut cookies = Vec::new();
+    let mut form = Vec::new();
+    for param in params {
+        let Some(key) = param["name"].as_str() else {
+            continue;
+        };
+        let example = param_example(doc, param);
+        match param["in"].as_str() {
+            Some("path") => {
+                // 有示例值的路径参数保存为变量
+                if let Some(example) = example {
+                    if !result.variables.iter().any(|v| v.key == key) {
+                        result.variables.push(PairUi::from_kv(key, &example));
+                    }
+                }
+            }
+            Some("query") => cfg.query.push(optional_pair(key, example, param)),
+            Some("header") => cfg.header.push(optional_pair(key, example, param)),
+            Some("cookie") => cookies.push(format!("{}={}", key, example.unwrap_or_default())),
+            Some("body") => set_swagger2_body(&mut cfg, &consumes(doc, operation), example_value(doc, &param["schema"], 0)),
+            Some("formData") => {
+                let value = if param["type"].as_str() == Some("file") {
+                    "@".to_owned()
+                } else {
+                    example.unwrap_or_default()
+                };
+                form.push((key.to_owned(), value, param["type"].as_str() == Some("file")));
+            }
+            _ => {}
+        }
+    }
+    if !cookies.is_empty() {
+        cfg.header.push(PairUi::from_kv("Cookie", &cookies.join("; ")));
+    }
+    if !form.is_empty() {
+        let multipart = form.iter().any(|(_, _, file)| *file)
+            || consumes(doc, operation).contains(&"multipart/form-data");
+        set_form_body(&mut cfg, form, multipart);
+    }
+
+    if !swagger2 {
+        let body = resolve(doc, &operation["requestBody"]);
+        if let Some(content) = body["content"].as_object() {
+            set_request_body(doc, &mut cfg, content);
+        }
+    }
+
+    let mut test = HttpTest::from_name(name);
+    test.request = cfg;
+    test
+}
+
+/// 非必填参数默认禁用
+fn optional_pair(key: &str, example: Option<String>, param: &Value) -> PairUi {
+    let mut pair = PairUi::from_kv(key, &example.unwrap_or_default());
+    pair.disable = !param["required"].as_bool().unwrap_or(false);
+    pair
+}
+
+fn param_example(doc: &Value, param: &Value) -> Option<String> {
+    let value = param
+        .get("example")
+        .or_else(|| {
+            param["examples"]
+                .as_object()
+                .and_then(|e| e.values().next())
+                .map(|e| &resolve(doc, e)["value"])
+        })
+        .or_else(|| param.get("x-example"))
+        .or_else(|| param.get("default"))
+        .or_else(|| param["enum"].get(0))
+        .cloned()
+        .or_else(|| param.get("schema").map(|s| example_value(doc, s, 0)))?;
+
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s),
+        v => Some(v.to_string()),
+    }
+}
+
+fn set_request_body(doc: &Value, cfg: &mut HttpRequestConfig, content: &Map<String, Value>) {
+    let find = |prefix: &str| content.iter().find(|(mime, _)| mime.starts_with(prefix));
+
+    if let Some((_, media)) = find("application/json").or_else(|| find("application/").filter(|(m, _)| m.ends_with("+json"))) {
+        set_json_body(cfg, media_example(doc, media));
+    } else if let Some((_, media)) = find("application/x-www-form-urlencoded") {
+        set_form_body(cfg, schema_fields(doc, media), false);
+    } else if let Some((_, media)) = find("multipart/form-data") {
+        set_form_body(cfg, schema_fields(doc, media), true);
+    } else if let Some((mime, media)) = find("application/xml").or_else(|| find("text/")) {
+        cfg.body_tab_ui = RequestBodyTab::Raw;
+        cfg.body_raw_type = if mime.contains("xml") {
+            RequestBodyRawType::XML
+        } else {
+            RequestBodyRawType::Text
+        };
+        cfg.body_raw = match media_example(doc, media) {
+            Value::String(s) => s,
+            Value::Null => String::new(),
+            v => v.to_string(),
+        };
+    } else if find("application/octet-stream").is_some() {
+        cfg.body_tab_ui = RequestBodyTab::Raw;
+        cfg.body_raw_type = RequestBodyRawType::BinaryFile;
+    }
+}
+
+/// Swagger 2 的 body 参数, 按 consumes 选择 JSON / XML / 文本
+fn set_swagger2_body(cfg: &mut HttpRequestConfig, consumes: &[&str], example: Value) {
+    let json = consumes.is_empty() || consumes.iter().any(|m| m.contains("json"));
+    if json {
+        return set_json_body(cfg, example);
+    }
+    cfg.body_tab_ui = RequestBodyTab::Raw;
+    if consumes.iter().any(|m| m.contains("xml")) {
+        cfg.body_raw_type = RequestBodyRawType::XML;
+    } else if consumes.contains(&"application/octet-stream") {
+        cfg.body_raw_type = RequestBodyRawType::BinaryFile;
+        return;
+    } else {
+        cfg.body_raw_type = RequestBodyRawType::Text;
+    }
+    cfg.body_raw = match example {
+        Value::String(s) => s,
+        Value::Null => String::new(),
+        v => v.to_string(),
+    };
+}
+
+fn set_json_body(cfg: &mut HttpRequestConfig, example: Value) {
+    cfg.body_tab_ui = RequestBodyTab::Raw;
+    cfg.body_raw_type = RequestBodyRawType::Json;
+    if !example.is_null() {
+        cfg.body_raw = serde_json::to_string_pretty(&example).unwrap_or_default();
+    }
+}
+
+fn set_form_body(cfg: &mut HttpRequestConfig, fields: Vec<(String, String, bool)>, multipart: bool) {
+    let pairs = fields.into_iter().map(|(k, v, _)| PairUi::from_kv(&k, &v)).collect();
+    if multipart {
+        cfg.body_tab_ui = RequestBodyTab::FormData;
+        cfg.body_form_data = pairs;
+    } else {
+        cfg.body_tab_ui = RequestBodyTab::Form;
+        cfg.body_form = pairs;
+    }
+}
+
+/// 表单字段 (名称, 示例值, 是否为文件)
+fn schema_fields(doc: &Value, media: &Value) -> Vec<(String, String, bool)> {
+    let schema = resolve(doc, &media["schema"]);
+    let example = media_example(doc, media);
+    schema["properties"]
+        .as_object()
+        .map(|props| {
+            props
+                .iter()
+                .map(|(name, prop)| {
+                    let prop = resolve(doc, prop);
+                    let file = prop["format"].as_str() == Some("binary");
+                    let value = match &example[name] {
+                        _ if file => "@".to_owned(),
+                        Value::String(s) => s.to_owned(),
+                        Value::Null => String::new(),
+                        v => v.to_string(),
+                    };
+                    (name.to_owned(), value, file)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn media_example(doc: &Value, media: &Value) -> Value {
+    if let Some(example) = media.get("example") {
+        return example.clone();
+    }
+    if let Some(example) = media["examples"].as_object().and_then(|e| e.values().next()) {
+        return resolve(doc, example)["value"].clone();
+    }
+    example_value(doc, &media["schema"], 0)
+}
+
+/// 根据 schema 生成示例值
+fn example_value(doc: &Value, schema: &Value, depth: usize) -> Value {
+    // 防止递归引用
+    if depth > 8 {
+        return Value::Null;
+    }
+    let schema = resolve(doc, schema);
+    if let Some(example) = schema.get("example").or_else(|| schema.get("default")) {
+        return example.clone();
+    }
+    if let Some(first) = schema["enum"].get(0) {
+        return first.clone();
+    }
+    if let Some(first) = schema["allOf"].as_array() {
+        let mut merged = Map::new();
+        for part in first {
+            if let Value::Object(obj) = example_value(doc, part, depth + 1) {
+                merged.extend(obj);
+            }
+        }
+        return Value::Object(merged);
+    }
+    if let Some(first) = schema["oneOf"].get(0).or_else(|| schema["anyOf"].get(0)) {
+        return example_value(doc, first, depth + 1);
+    }
+
+    match schema["type"].as_str() {
+        Some("object") | None if schema.get("properties").is_some() => Value::Object(
+            schema["properties"]
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(|(k, v)| (k.to_owned(), example_value(doc, v, depth + 1)))
+                .collect(),
+        ),
+        Some("object") => Value::Object(Map::new()),
+        Some("array") => Value::Array(vec![example_value(doc, &schema["items"], depth + 1)]),
+        Some("string") => Value::String(match schema["format"].as_str() {
+            Some("date-time") => "1970-01-01T00:00:00Z".to_owned(),
+            Some("date") => "1970-01-01".to_owned(),
+            Some("uuid") => "00000000-0000-0000-0000-000000000000".to_owned(),
+            Some("email") => "user@example.com".to_owned(),
+            _ => "string".to_owned(),
+        }),
+        Some("integer") | Some("number") => Value::from(0),
+        Some("boolean") => Value::Bool(false),
+        _ => Value::Null,
+    }
+}
+
+/// 解析文档内的 `$ref`
+fn resolve<'a>(doc: &'a Value, value: &'a Value) -> &'a Value {
+    let mut value = value;
+    // 限制次数, 防止循环引用
+    for _ in 0..16 {
+        match value["$ref"].as_str().and_then(|r| r.strip_prefix('#')) {
+            Some(pointer) => match doc.pointer(pointer) {
+                Some(target) => value = target,
+                None => break,
+            },
+            None => break,
+        }
+    }
+    value
+}
@@ -0,0 +1,341 @@
+//! 导入 Postman v2.1 集合和环境
+
+use anyhow::{bail, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::{
+    Environment, Group, HttpRequestConfig, HttpTest, ImportResult, Method, PairUi,
+    RequestBodyRawType, RequestBodyTab,
+};
+
+/// 导入集合: 文件夹对应 Group, 请求对应 HttpTest, 集合变量对应全局变量
+pub fn import_collection(data: &str) -> Result<ImportResult> {
+    let json: Value = serde_json::from_str(data)?;
+    let Some(items) = json["item"].as_array() else {
+        bail!("不是 Postman 集合: 缺少 item");
+    };
+
+    let collection_name = json["info"]["name"].as_str().unwrap_or("Postman").to_owned();
+    let mut result = ImportResult {
+        variables: pairs(&json["variable"]),
+        ..Default::default()
+    };
+
+    if has_scripts(&json["event"]) {
+        result
+            .warnings
+            .push(format!("集合 {} 的脚本未导入", collection_name));
+    }
+
+    // 顶层请求放到以集合命名的组中
+    let mut root = Group::from_name(collection_name);
+    walk_items(items, None, &mut root.childrent, &mut result);
+    if !root.childrent.is_empty() {
+        result.groups.insert(0, root);
+    }
+
+    Ok(result)
+}
+
+/// 导入环境文件
+pub fn import_environment(data: &str) -> Result<Environment> {
+    let json: Value = serde_json::from_str(data)?;
+    let Some(values) = json["values"].as_array() else {
+        bail!("不是 Postman 环境: 缺少 values");
+    };
+
+    let mut env = Environment::from_name(json["name"].as_str().unwrap_or("Postman"));
+    env.variables = values
+        .iter()
+        .filter_map(|v| {
+            let key = v["key"].as_str()?;
+            let mut pair = PairUi::from_kv(key, &value_string(&v["value"]));
+            pair.disable = v["enabled"].as_bool() == Some(false);
+            Some(pair)
+        })
+        .collect();
+    Ok(env)
+}
+
+/// 文件夹转为 Group, 嵌套文件夹展开为 "父 / 子"
+fn walk_items(items: &[Value], path: Option<&str>, requests: &mut Vec<HttpTest>, result: &mut ImportResult) {
+    for item in items {
+        let name = item["name"].as_str().unwrap_or("Untitled");
+
+        if let Some(children) = item["item"].as_array() {
+            let full_name = match path {
+                Some(path) => format!("{} / {}", path, name),
+                None => name.to_owned(),
+            };
+            if has_scripts(&item["event"]) {
+                result.warnings.push(format!("文件夹 {} 的脚本未导入", full_name));
+            }
+
+            // 先占位, 保证父文件夹排在子文件夹之前
+            let index = result.groups.len();
+            result.groups.push(Group::from_name(full_name.clone()));
+            let mut tests = Vec::new();
+            walk_items(children, Some(&full_name), &mut tests, result);
+            result.groups[index].childrent = tests;
+        } else if item.get("request").is_some() {
+            requests.push(convert_request(name, item, result));
+        }
+    }
+}
+
+fn convert_request(name: &str, item: &Value, result: &mut ImportResult) -> HttpTest {
+    let request = &item["request"];
+    let mut cfg = HttpRequestConfig::default();
+
+    // request 可以直接是 URL 字符串
+    if let Some(url) = request.as_str() {
+        cfg.url = url.to_owned();
+        let mut test = HttpTest::from_name(name.to_owned());
+        test.request = cfg;
+        return test;
+    }
+
+    let method = request["method"].as_str().unwrap_or("GET");
+    cfg.method = Method::from_name(method).unwrap_or_else(|| {
+        result.warnings.push(format!("{}: 不支持的方法 {}", name, method));
+        Method::GET
+    });
+
+    let (url, query) = convert_url(&request["url"]);
+    cfg.url = url;
+    cfg.query = query;
+    cfg.header = pairs(&request["header"]);
+
+    let body = &request["body"];
+    match body["mode"].as_str() {
+        Some("raw") => {
+            cfg.body_tab_ui = RequestBodyTab::Raw;
+            cfg.body_raw = body["raw"].as_str().unwrap_or_default().to_owned();
+            cfg.body_raw_type = match body["options"]["raw"]["language"].as_str() {
+                Some("json") => RequestBodyRawType::Json,
+                Some("xml") | Some("html") => RequestBodyRawType::XML,
+                Some(_) => RequestBodyRawType::Text,
+                None if cfg.body_raw.trim_start().starts_with(['{', '[']) => RequestBodyRawType::Json,
+                None => RequestBodyRawType::Text,
+            };
+        }
+        Some("urlencoded") => {
+            cfg.body_tab_ui = RequestBodyTab::Form;
+            cfg.body_form = pairs(&body["urlencoded"]);
+        }
+        Some("formdata") => {
+            cfg.body_tab_ui = RequestBodyTab::FormData;
+            cfg.body_form_data = body["formdata"]
+                .as_array()
+                .map(|parts| parts.iter().filter_map(form_data_part).collect())
+                .unwrap_or_default();
+        }
+        Some("file") => {
+            cfg.body_tab_ui = RequestBodyTab::Raw;
+            cfg.body_raw_type = RequestBodyRawType::BinaryFile;
+            cfg.body_raw = body["file"]["src"].as_str().unwrap_or_default().to_owned();
+        }
+        Some(mode) => result.warnings.push(format!("{}: 不支持的 body 类型 {}", name, mode)),
+        None => {}
+    }
+
+    if let Some(events) = item["event"].as_array() {
+        for event in events {
+            let script = script_source(&event["script"]["exec"]);
+            if script.trim().is_empty() {
+                continue;
+            }
+            let (translated, untranslated) = translate_script(&script);
+            if untranslated > 0 {
+                result
+                    .warnings
+                    .push(format!("{}: 脚本有 {} 行未能转换, 已注释为 TODO", name, untranslated));
+            }
+            match event["listen"].as_str() {
+                Some("prerequest") => cfg.pre_request_script = translated,
+                Some("test") => cfg.post_response_script = translated,
+                _ => continue,
+            }
+            cfg.script_enabled = true;
+        }
+    }
+
+    let mut test = HttpTest::from_name(name.to_owned());
+    test.request = cfg;
+    test
+}
+
+/// url 可以是字符串或对象, 路径变量 `:id` 转为其值或 `{{id}}`
+fn convert_url(url: &Value) -> (String, Vec<PairUi>) {
+    if let Some(raw) = url.as_str() {
+        return match raw.split_once('?') {
+            Some((base, query)) => (
+                base.to_owned(),
+                query
+                    .split('&')
+                    .filter(|kv| !kv.is_empty())
+                    .map(|kv| match kv.split_once('=') {
+                        Some((k, v)) => PairUi::from_kv(k, v),
+                        None => PairUi::from_kv(kv, ""),
+                    })
+                    .collect(),
+            ),
+            None => (raw.to_owned(), Vec::new()),
+        };
+    }
+
+    let raw = url["raw"].as_str().unwrap_or_default();
+    let mut base = raw.split('?').next().unwrap_or(raw).to_owned();
+
+    if let Some(variables) = url["variable"].as_array() {
+        for var in variables {
+            let Some(key) = var["key"].as_str() else {
+                continue;
+            };
+            let value = value_string(&var["value"]);
+            let replacement = if value.is_empty() {
+                format!("{{{{{}}}}}", key)
+            } else {
+                value
+            };
+            base = replace_path_variable(&base, key, &replacement);
+        }
+    }
+
+    (base, pairs(&url["query"]))
+}
+
+fn replace_path_variable(url: &str, key: &str, value: &str) -> String {
+    let pattern = format!(":{}", key);
+    url.split('/')
+        .map(|segment| if segment == pattern { value } else { segment })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn form_data_part(part: &Value) -> Option<PairUi> {
+    let key = part["key"].as_str()?;
+    let value = if part["type"].as_str() == Some("file") {
+        // 使用 @path 表示文件
+        let srcs: Vec<String> = match &part["src"] {
+            Value::Array(arr) => arr.iter().filter_map(|s| s.as_str()).map(|s| s.to_owned()).collect(),
+            Value::String(s) => vec![s.to_owned()],
+            _ => Vec::new(),
+        };
+        srcs.iter().map(|s| format!("@{}", s)).collect::<Vec<_>>().join(" ")
+    } else {
+        value_string(&part["value"])
+    };
+    let mut pair = PairUi::from_kv(key, &value);
+    pair.disable = part["disabled"].as_bool().unwrap_or(false);
+    Some(pair)
+}
+
+/// key/value/disabled 数组
+fn pairs(value: &Value) -> Vec<PairUi> {
+    value
+        .as_array()
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|kv| {
+                    let key = kv["key"].as_str()?;
+                    let mut pair = PairUi::from_kv(key, &value_string(&kv["value"]));
+                    pair.disable = kv["disabled"].as_bool().unwrap_or(false);
+                    Some(pair)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn value_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.to_owned(),
+        Value::Null => String::new(),
+        v => v.to_string(),
+    }
+}
+
+fn has_scripts(events: &Value) -> bool {
+    events
+        .as_array()
+        .map(|events| {
+            events
+                .iter()
+                .any(|e| !script_source(&e["script"]["exec"]).trim().is_empty())
+        })
+        .unwrap_or(false)
+}
+
+fn script_source(exec: &Value) -> String {
+    match exec {
+        Value::Array(lines) => lines
+            .iter()
+            .filter_map(|l| l.as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::String(s) => s.to_owned(),
+        _ => String::new(),
+    }
+}
+
+/// 尽量把 Postman 脚本转换为 Rhai, 返回 (脚本, 未能转换的行数)
+///
+/// 无法转换的行会被注释并标记 `TODO(postman)`
+pub fn translate_script(script: &str) -> (String, usize) {
+    lazy_static! {
+        static ref RULES: Vec<(Regex, &'static str)> = [
+            (r"pm\.(?:environment|globals|collectionVariables|variables)\.set\(\s*([^,]+?)\s*,\s*(.+?)\s*\)", "vars[$1] = $2"),
+            (r"pm\.(?:environment|globals|collectionVariables|variables)\.get\(\s*([^)]+?)\s*\)", "vars[$1]"),
+            (r"pm\.(?:environment|globals|collectionVariables|variables)\.unset\(\s*([^)]+?)\s*\)", "vars.remove($1)"),
+            (r"pm\.response\.json\(\)", "parse_json(response.body)"),
+            (r"pm\.response\.text\(\)", "response.body"),
+            (r"pm\.response\.(?:code|status)\b", "response.status"),
+            (r"pm\.response\.responseTime\b", "response.duration"),
+            (r"pm\.response\.headers\.get\(\s*([^)]+?)\s*\)", "response.headers[$1.to_lower()]"),
+            (r"pm\.response\.to\.have\.status\(\s*([^)]+?)\s*\)", "expect(response.status).to_equal($1)"),
+            (r"pm\.request\.url\b(?:\.toString\(\))?", "request.url"),
+            (r"pm\.request\.method\b", "request.method"),
+            (r"pm\.request\.body\.raw\b", "request.body"),
+            (r"pm\.test\(", "test("),
+            (r"pm\.expect\(", "expect("),
+            (r"\.to\.(?:deep\.)?(?:eql|equal|equals|eq)\(", ".to_equal("),
+            (r"\.to\.(?:include|contain|have\.property)\(", ".to_contain("),
+            (r"\.to\.be\.(?:below|lessThan|lt)\(", ".to_be_below("),
+            (r"\.to\.match\(\s*/(.*)/[gimsuy]*\s*\)", ".to_match(\"$1\")"),
+            (r"function\s*\(\s*\)\s*\{", "|| {"),
+            (r"\(\s*\)\s*=>\s*\{", "|| {"),
+            (r"JSON\.parse\(", "parse_json("),
+            (r"JSON\.stringify\(", "to_json("),
+            (r"console\.log\(", "console_log("),
+            (r"\b(?:var|const)\s+", "let "),
+            (r"===", "=="),
+            (r"!==", "!="),
+        ]
+        .into_iter()
+        .map(|(re, rep)| (Regex::new(re).unwrap(), rep))
+        .collect();
+        static ref UNSUPPORTED: Regex = Regex::new(r"\bpm\.|\bpostman\.|\brequire\(|\bfunction\b|=>|\bnew\s").unwrap();
+    }
+
+    let mut untranslated = 0;
+    let lines: Vec<String> = script
+        .lines()
+        .map(|line| {
+            let mut line = line.to_owned();
+            for (re, rep) in RULES.iter() {
+                line = re.replace_all(&line, *rep).into_owned();
+            }
+            if UNSUPPORTED.is_match(&line) && !line.trim_start().starts_with("//") {
+                untranslated += 1;
+                format!("// TODO(postman): {}", line)
+            } else {
+                line
+            }
+        })
+        .collect();
+
+    (lines.join("\n"), untranslated)
+}
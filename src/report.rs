@@ -0,0 +1,280 @@
+//! 批量发送结果的导出和基线对比
+
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::RequestStats;
+
+/// 一次批量发送的结果, 导出为 JSON 后可作为基线
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoadReport {
+    /// 测试名
+    pub test: String,
+    /// 导出时间
+    pub created_at: String,
+    pub summary: ReportSummary,
+    pub status_codes: BTreeMap<u16, usize>,
+    /// 错误类型 -> 次数
+    pub errors: BTreeMap<String, usize>,
+    /// 每秒统计
+    pub timeline: Vec<ReportSecond>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReportSummary {
+    pub requests: usize,
+    pub success: usize,
+    pub failed: usize,
+    pub assertion_failed: usize,
+    /// 失败请求占比 (%)
+    pub error_rate: f64,
+    /// 总耗时 (秒)
+    pub duration: Option<f64>,
+    pub qps: Option<f64>,
+    pub min_ms: Option<f64>,
+    pub avg_ms: Option<f64>,
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+    pub upload_bytes: u64,
+    pub download_bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReportSecond {
+    pub second: usize,
+    pub requests: u64,
+    pub errors: u64,
+    pub upload_bytes: u64,
+    pub download_bytes: u64,
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+}
+
+impl LoadReport {
+    pub fn from_stats(test: &str, stats: &RequestStats) -> Self {
+        let requests = stats.total_requests();
+        Self {
+            test: test.to_owned(),
+            created_at: crate::util::iso_timestamp(std::time::SystemTime::now()),
+            summary: ReportSummary {
+                requests,
+                success: stats.success,
+                failed: stats.failed,
+                assertion_failed: stats.assertion_failed,
+                error_rate: if requests == 0 {
+                    0.0
+                } else {
+                    stats.failed as f64 / requests as f64 * 100.0
+                },
+                duration: stats.total_duration(),
+                qps: stats.qps(),
+                min_ms: stats.min_response_time(),
+                avg_ms: stats.avg_response_time(),
+                p50_ms: stats.percentile(50.0),
+                p95_ms: stats.percentile(95.0),
+                p99_ms: stats.percentile(99.0),
+                max_ms: stats.max_response_time(),
+                upload_bytes: stats.total_upload_bytes,
+                download_bytes: stats.total_download_bytes,
+            },
+            status_codes: stats.status_codes.clone(),
+            errors: stats
+                .errors
+                .iter()
+                .map(|(kind, summary)| (kind.as_ref().to_owned(), summary.count))
+                .collect(),
+            timeline: stats
+                .timeline
+                .buckets()
+                .iter()
+                .enumerate()
+                .map(|(second, bucket)| {
+                    let [p50_ms, p95_ms, p99_ms] = stats.timeline.latency(second);
+                    ReportSecond {
+                        second,
+                        requests: bucket.requests,
+                        errors: bucket.errors,
+                        upload_bytes: bucket.upload_bytes,
+                        download_bytes: bucket.download_bytes,
+                        p50_ms,
+                        p95_ms,
+                        p99_ms,
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    pub fn from_json(data: &str) -> Result<Self> {
+        Ok(serde_json::from_str(data)?)
+    }
+
+    /// 读取 JSON 格式的结果, CSV 缺少对比所需的字段, 不能作为基线
+    pub fn load(path: &str) -> Result<Self> {
+        if path.to_lowercase().ends_with(".csv") {
+            bail!("{} 是 CSV 文件, 基线需要使用导出的 JSON 结果", path);
+        }
+        let data = std::fs::read_to_string(path).with_context(|| format!("读取 {} 失败", path))?;
+        Self::from_json(&data).with_context(|| format!("{} 不是有效的结果文件", path))
+    }
+
+    /// 按扩展名写为 CSV 或 JSON
+    pub fn save(&self, path: &str) -> Result<()> {
+        let data = if path.to_lowercase().ends_with(".csv") {
+            self.to_csv()
+        } else {
+            self.to_json()?
+        };
+        std::fs::write(path, data).with_context(|| format!("写入 {} 失败", path))
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// 汇总指标 (metric,value) 和每秒统计两个表, 中间以空行分隔
+    pub fn to_csv(&self) -> String {
+        let opt = |v: Option<f64>| v.map(|v| format!("{:.3}", v)).unwrap_or_default();
+        let s = &self.summary;
+        let mut out = String::from("metric,value\n");
+        let rows = [
+            ("requests", s.requests.to_string()),
+            ("success", s.success.to_string()),
+            ("failed", s.failed.to_string()),
+            ("assertion_failed", s.assertion_failed.to_string()),
+            ("error_rate", format!("{:.3}", s.error_rate)),
+            ("duration_s", opt(s.duration)),
+            ("qps", opt(s.qps)),
+            ("min_ms", opt(s.min_ms)),
+            ("avg_ms", opt(s.avg_ms)),
+            ("p50_ms", opt(s.p50_ms)),
+            ("p95_ms", opt(s.p95_ms)),
+            ("p99_ms", opt(s.p99_ms)),
+            ("max_ms", opt(s.max_ms)),
+            ("upload_bytes", s.upload_bytes.to_string()),
+            ("download_bytes", s.download_bytes.to_string()),
+        ];
+        for (metric, value) in rows {
+            out.push_str(&format!("{},{}\n", metric, value));
+        }
+        for (code, count) in &self.status_codes {
+            out.push_str(&format!("status_{},{}\n", code, count));
+        }
+        for (kind, count) in &self.errors {
+            out.push_str(&format!("\"error_{}\",{}\n", kind, count));
+        }
+
+        out.push_str("\nsecond,requests,errors,upload_bytes,download_bytes,p50_ms,p95_ms,p99_ms\n");
+        for r in &self.timeline {
+            out.push_str(&format!(
+                "{},{},{},{},{},{},{},{}\n",
+                r.second,
+                r.requests,
+                r.errors,
+                r.upload_bytes,
+                r.download_bytes,
+                opt(r.p50_ms),
+                opt(r.p95_ms),
+                opt(r.p99_ms)
+            ));
+        }
+        out
+    }
+}
+
+/// 判定为退化的阈值, 延迟和 QPS 为相对变化 (%), 错误率为百分点
+#[derive(Debug, Clone)]
+pub struct Thresholds {
+    pub avg_increase: f64,
+    pub p95_increase: f64,
+    pub p99_increase: f64,
+    pub qps_decrease: f64,
+    pub error_rate_increase: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            avg_increase: 20.0,
+            p95_increase: 20.0,
+            p99_increase: 20.0,
+            qps_decrease: 10.0,
+            error_rate_increase: 1.0,
+        }
+    }
+}
+
+/// 一个指标的对比结果
+#[derive(Debug, Clone)]
+pub struct MetricDiff {
+    pub name: &'static str,
+    pub baseline: Option<f64>,
+    pub current: Option<f64>,
+    /// 相对变化 (%), 错误率为百分点
+    pub change: Option<f64>,
+    /// change 是否为相对变化
+    pub relative: bool,
+    pub regressed: bool,
+}
+
+impl MetricDiff {
+    pub fn change_text(&self) -> String {
+        match self.change {
+            Some(change) if self.relative => format!("{:+.1}%", change),
+            Some(change) => format!("{:+.2}", change),
+            None => "-".to_owned(),
+        }
+    }
+}
+
+/// 与基线对比
+pub fn compare(baseline: &LoadReport, current: &LoadReport, thresholds: &Thresholds) -> Vec<MetricDiff> {
+    let (b, c) = (&baseline.summary, &current.summary);
+    let relative = |name, baseline: Option<f64>, current: Option<f64>, limit: f64, higher_is_worse: bool| {
+        let change = match (baseline, current) {
+            (Some(b), Some(c)) if b > 0.0 => Some((c - b) / b * 100.0),
+            _ => None,
+        };
+        let regressed = change.is_some_and(|change| {
+            if higher_is_worse {
+                change > limit
+            } else {
+                -change > limit
+            }
+        });
+        MetricDiff {
+            name,
+            baseline,
+            current,
+            change,
+            relative: true,
+            regressed,
+        }
+    };
+
+    let error_rate_change = c.error_rate - b.error_rate;
+    vec![
+        relative("QPS", b.qps, c.qps, thresholds.qps_decrease, false),
+        relative("Avg (ms)", b.avg_ms, c.avg_ms, thresholds.avg_increase, true),
+        relative("P50 (ms)", b.p50_ms, c.p50_ms, f64::INFINITY, true),
+        relative("P95 (ms)", b.p95_ms, c.p95_ms, thresholds.p95_increase, true),
+        relative("P99 (ms)", b.p99_ms, c.p99_ms, thresholds.p99_increase, true),
+        relative("Max (ms)", b.max_ms, c.max_ms, f64::INFINITY, true),
+        MetricDiff {
+            name: "错误率 (%)",
+            baseline: Some(b.error_rate),
+            current: Some(c.error_rate),
+            change: Some(error_rate_change),
+            relative: false,
+            regressed: error_rate_change > thresholds.error_rate_increase,
+        },
+    ]
+}
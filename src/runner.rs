@@ -0,0 +1,240 @@
+//! 无界面运行器
+//!
+//! 按顺序执行项目中的 Group / HttpTest, 供命令行 (CI) 使用
+
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use tokio::sync::mpsc;
+
+use crate::assertion::{self, AssertionResult};
+use crate::script_engine::CompiledScripts;
+use crate::{data_file, load, util, Group, HttpTest, Method, PairUi, Project};
+
+/// 运行过滤条件, 为空表示全部运行
+#[derive(Debug, Clone, Default)]
+pub struct RunFilter {
+    /// 组名
+    pub groups: Vec<String>,
+    /// 测试名, 支持 `test` 或 `group/test`
+    pub tests: Vec<String>,
+}
+
+impl RunFilter {
+    pub fn match_group(&self, group: &Group) -> bool {
+        self.groups.is_empty() || self.groups.iter().any(|name| name == &group.name)
+    }
+
+    pub fn match_test(&self, group: &Group, test: &HttpTest) -> bool {
+        self.tests.is_empty()
+            || self.tests.iter().any(|name| match name.split_once('/') {
+                Some((group_name, test_name)) => group_name == group.name && test_name == test.name,
+                None => name == &test.name,
+            })
+    }
+}
+
+/// 单个测试的执行结果
+#[derive(Debug, Clone)]
+pub struct TestOutcome {
+    pub group: String,
+    pub test: String,
+    /// HTTP 状态码, 请求失败时为 None
+    pub status: Option<u16>,
+    /// 响应时间(ms)
+    pub duration: u128,
+    /// 请求错误信息
+    pub error: Option<String>,
+    /// 断言和脚本 test() 结果
+    pub assertions: Vec<AssertionResult>,
+}
+
+impl TestOutcome {
+    /// 配置了断言时以断言为准, 否则要求 2xx
+    pub fn passed(&self) -> bool {
+        if self.error.is_some() {
+            return false;
+        }
+        if self.assertions.is_empty() {
+            self.status.is_some_and(|s| (200..300).contains(&s))
+        } else {
+            self.assertions.iter().all(|r| r.passed)
+        }
+    }
+}
+
+/// 一次运行的汇总
+#[derive(Debug, Clone, Default)]
+pub struct RunReport {
+    pub outcomes: Vec<TestOutcome>,
+    /// 跳过的测试数 (例如 WS)
+    pub skipped: usize,
+}
+
+impl RunReport {
+    pub fn passed(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.passed()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.outcomes.len() - self.passed()
+    }
+
+    /// 请求成功但断言未通过的测试数
+    pub fn assertion_failed(&self) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|o| o.error.is_none() && o.assertions.iter().any(|r| !r.passed))
+            .count()
+    }
+
+    pub fn success(&self) -> bool {
+        self.failed() == 0
+    }
+}
+
+/// 解析 `key=value` 形式的变量覆盖
+pub fn parse_var_override(s: &str) -> Result<PairUi> {
+    match s.split_once('=') {
+        Some((k, v)) if !k.trim().is_empty() => Ok(PairUi::from_kv(k.trim(), v)),
+        _ => bail!("变量格式错误, 应为 key=value: {}", s),
+    }
+}
+
+/// 按顺序执行项目中匹配的测试
+///
+/// 每个请求返回的 `modified_vars` 会合并到后续请求使用的变量中,
+/// 设置了数据文件的测试对每行数据执行一次, `on_outcome` 在每个请求完成后调用
+pub async fn run_project(
+    project: &Project,
+    filter: &RunFilter,
+    overrides: &[PairUi],
+    mut on_outcome: impl FnMut(&TestOutcome),
+) -> RunReport {
+    let mut vars = project.resolved_variables();
+    util::merge_vars(&mut vars, overrides);
+
+    let mut report = RunReport::default();
+
+    for group in project.groups.iter().filter(|g| filter.match_group(g)) {
+        for test in group.childrent.iter().filter(|t| filter.match_test(group, t)) {
+            if test.request.method == Method::WS {
+                report.skipped += 1;
+                continue;
+            }
+
+            let rows = match test.data_file.trim() {
+                "" => vec![None],
+                path => match data_file::load_data_file(path, project.dir.as_deref()) {
+                    Ok(rows) => rows.into_iter().map(Some).collect(),
+                    Err(err) => {
+                        let outcome = TestOutcome {
+                            group: group.name.clone(),
+                            test: test.name.clone(),
+                            status: None,
+                            duration: 0,
+                            error: Some(format!("{:#}", err)),
+                            assertions: Vec::new(),
+                        };
+                        on_outcome(&outcome);
+                        report.outcomes.push(outcome);
+                        continue;
+                    }
+                },
+            };
+
+            let scripts = project.compile_scripts(group, &test.request);
+            for (i, row) in rows.iter().enumerate() {
+                let name = match row {
+                    Some(_) => format!("{} [{}]", test.name, i + 1),
+                    None => test.name.clone(),
+                };
+                let mut row_vars = vars.clone();
+                if let Some(row) = row {
+                    util::merge_vars(&mut row_vars, row);
+                }
+
+                let outcome = match util::ITERATION
+                    .scope(i, util::http_send_with_scripts(&test.request, &row_vars, &scripts)).await {
+                    Ok(response) => {
+                        if let Some(modified_vars) = &response.modified_vars {
+                            let mut modified_vars = modified_vars.clone();
+                            if let Some(row) = row {
+                                util::drop_row_vars(&mut modified_vars, row, &vars);
+                            }
+                            util::merge_vars(&mut vars, &modified_vars);
+                        }
+                        let mut assertions = assertion::evaluate_all(&test.assertions, &response);
+                        assertions.extend(response.script_tests.iter().cloned());
+                        TestOutcome {
+                            group: group.name.clone(),
+                            test: name,
+                            status: Some(response.status.as_u16()),
+                            duration: response.duration,
+                            error: None,
+                            assertions,
+                        }
+                    }
+                    Err(err) => TestOutcome {
+                        group: group.name.clone(),
+                        test: name,
+                        status: None,
+                        duration: 0,
+                        error: Some(err.to_string()),
+                        assertions: Vec::new(),
+                    },
+                };
+
+                on_outcome(&outcome);
+                report.outcomes.push(outcome);
+            }
+        }
+    }
+
+    report
+}
+
+/// 按测试保存的批量发送设置运行一次, 返回运行后的测试 (含统计和 Data 模式每行的结果)
+///
+/// scripts 一般由 `Project::compile_scripts` 得到, 数据文件的相对路径相对于 base_dir
+pub async fn run_load(
+    test: &HttpTest,
+    scripts: CompiledScripts,
+    vars: &[PairUi],
+    base_dir: Option<&Path>,
+) -> Result<HttpTest> {
+    if test.request.method == Method::WS {
+        bail!("{}: WS 请求不支持批量发送", test.name);
+    }
+    let plan = test.load_plan(base_dir)?;
+    if plan.is_empty() {
+        bail!("{}: 批量发送设置为空", test.name);
+    }
+
+    let mut test = test.clone();
+    let id = test.send_before_init(&plan);
+    test.stats.pending = 0;
+    test.stats.sending = test.send_count;
+
+    let (tx, mut rx) = mpsc::channel(10000);
+    tokio::spawn(load::run(
+        id,
+        Arc::new(test.request.clone()),
+        Arc::new(vars.to_vec()),
+        Arc::new(test.assertions.clone()),
+        Arc::new(scripts),
+        plan,
+        tx,
+    ));
+    while let Some(event) = rx.recv().await {
+        match event {
+            load::LoadEvent::Response { stage, row, completed_at, result, .. } => {
+                test.record_result(stage, row, &result, completed_at)
+            }
+            load::LoadEvent::Finished { .. } => break,
+        }
+    }
+    test.finish_run();
+    Ok(test)
+}
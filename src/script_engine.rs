@@ -1,9 +1,181 @@
 #![allow(warnings, unused)]
 
 use anyhow::{bail, Result};
-use rhai::{Dynamic, Engine, Map, Scope, AST};
+use rhai::{Dynamic, Engine, EvalAltResult, FnPtr, Map, NativeCallContext, Scope, AST};
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+
+use crate::assertion::AssertionResult;
+use crate::HttpRequestConfig;
+
+static SHARED_ENGINE: OnceLock<ScriptEngine> = OnceLock::new();
+
+thread_local! {
+    /// 当前线程正在执行的脚本中 test() 的结果
+    static TEST_RESULTS: RefCell<Vec<AssertionResult>> = const { RefCell::new(Vec::new()) };
+    /// 当前线程正在执行的脚本的控制台输出
+    static CONSOLE_OUTPUT: RefCell<Vec<(ConsoleLevel, String)>> = const { RefCell::new(Vec::new()) };
+    /// 当前线程编译脚本时可导入的模块库
+    static LIBRARY: RefCell<Option<ScriptLibrary>> = const { RefCell::new(None) };
+    /// 正在加载的模块, 用于检测循环导入
+    static LOADING_MODULES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// 项目中保存的脚本模块, 脚本中通过 `import "name" as m;` 使用
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScriptModule {
+    pub name: String,
+    pub source: String,
+}
+
+/// 脚本可导入的模块
+///
+/// `import "name"` 先查找项目中同名的模块, 再查找 base_dir 下的 `name.rhai` 文件
+#[derive(Debug, Clone, Default)]
+pub struct ScriptLibrary {
+    pub modules: Vec<ScriptModule>,
+    /// 模块文件的目录, 一般为项目文件所在目录, None 时为当前目录
+    pub base_dir: Option<PathBuf>,
+}
+
+impl ScriptLibrary {
+    /// 模块文件路径, 未写扩展名时补上 .rhai
+    fn file_path(&self, name: &str) -> PathBuf {
+        let mut path = self.base_dir.clone().unwrap_or_default().join(name);
+        if path.extension().is_none() {
+            path.set_extension("rhai");
+        }
+        path
+    }
+
+    /// 模块源码, 找不到时返回说明查找过的位置的错误信息
+    fn source(&self, name: &str) -> std::result::Result<String, String> {
+        if let Some(module) = self.modules.iter().find(|m| m.name == name) {
+            return Ok(module.source.clone());
+        }
+        let path = self.file_path(name);
+        std::fs::read_to_string(&path).map_err(|err| {
+            format!(
+                "{} (项目中没有该模块, 读取 {} 失败: {})",
+                name,
+                path.display(),
+                err
+            )
+        })
+    }
+}
+
+/// 从当前线程的 ScriptLibrary 加载模块
+///
+/// 脚本编译为自包含的 AST, 导入在编译时解析, 执行时不再依赖模块库
+struct LibraryResolver;
+
+impl rhai::ModuleResolver for LibraryResolver {
+    fn resolve(
+        &self,
+        engine: &Engine,
+        _source: Option<&str>,
+        path: &str,
+        pos: rhai::Position,
+    ) -> std::result::Result<rhai::Shared<rhai::Module>, Box<EvalAltResult>> {
+        let source = LIBRARY
+            .with(|l| l.borrow().clone().unwrap_or_default().source(path))
+            .map_err(|msg| EvalAltResult::ErrorModuleNotFound(msg, pos))?;
+
+        if LOADING_MODULES.with(|m| m.borrow().iter().any(|name| name == path)) {
+            return Err(EvalAltResult::ErrorInModule(
+                path.to_string(),
+                EvalAltResult::ErrorRuntime(format!("循环导入模块 {}", path).into(), pos).into(),
+                pos,
+            )
+            .into());
+        }
+        LOADING_MODULES.with(|m| m.borrow_mut().push(path.to_string()));
+        let module = engine
+            .compile(&source)
+            .map_err(|e| e.into())
+            .and_then(|ast| rhai::Module::eval_ast_as_new(Scope::new(), &ast, engine));
+        LOADING_MODULES.with(|m| m.borrow_mut().pop());
+
+        module
+            .map(|mut module| {
+                module.build_index();
+                module.set_id(path);
+                module.into()
+            })
+            .map_err(|err| EvalAltResult::ErrorInModule(path.to_string(), err, pos).into())
+    }
+}
+
+/// 脚本阶段
+#[derive(Debug, Clone, Copy, PartialEq, strum::AsRefStr)]
+pub enum ScriptPhase {
+    #[strum(serialize = "Pre-request")]
+    PreRequest,
+    #[strum(serialize = "Post-response")]
+    PostResponse,
+}
+
+/// 脚本所属范围, 执行顺序为 项目 → 组 → 请求
+#[derive(Debug, Clone, Copy, PartialEq, strum::AsRefStr)]
+pub enum ScriptScope {
+    Project,
+    Group,
+    Request,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, strum::AsRefStr)]
+pub enum ConsoleLevel {
+    Log,
+    Error,
+}
+
+/// 一行控制台输出
+#[derive(Debug, Clone)]
+pub struct ConsoleLine {
+    pub scope: ScriptScope,
+    pub phase: ScriptPhase,
+    pub level: ConsoleLevel,
+    pub message: String,
+}
+
+/// console_log 输出
+fn console_log(message: String) {
+    CONSOLE_OUTPUT.with(|c| c.borrow_mut().push((ConsoleLevel::Log, message)));
+}
+
+/// 脚本内部函数的错误
+fn console_error(message: String) {
+    CONSOLE_OUTPUT.with(|c| c.borrow_mut().push((ConsoleLevel::Error, message)));
+}
+
+/// 取出控制台输出, 标记所属阶段
+fn take_console(phase: ScriptPhase) -> Vec<ConsoleLine> {
+    CONSOLE_OUTPUT.with(|c| c.take())
+        .into_iter()
+        .map(|(level, message)| ConsoleLine {
+            scope: ScriptScope::Request,
+            phase,
+            level,
+            message,
+        })
+        .collect()
+}
+
+/// 脚本错误信息, 带行列号
+fn error_with_position(err: Box<EvalAltResult>) -> String {
+    let mut err = *err;
+    let pos = err.take_position();
+    match (pos.line(), pos.position()) {
+        (Some(line), Some(column)) => format!("{} (行 {}, 列 {})", err, line, column),
+        (Some(line), None) => format!("{} (行 {})", err, line),
+        _ => err.to_string(),
+    }
+}
 
 /// 脚本执行上下文 - 请求前
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,8 +220,18 @@ pub struct ScriptResult {
     pub error: Option<String>,
     /// 修改后的上下文
     pub context: ScriptContext,
-    /// 控制台输出
-    pub console_output: Vec<String>,
+    /// 控制台输出, 失败时最后一行为脚本错误
+    pub console_output: Vec<ConsoleLine>,
+    /// test() 的结果
+    pub tests: Vec<AssertionResult>,
+    /// 执行耗时
+    pub duration: std::time::Duration,
+}
+
+/// expect(value) 返回的对象, 用于链式调用匹配器
+#[derive(Debug, Clone)]
+pub struct Expectation {
+    value: Dynamic,
 }
 
 /// 统一的脚本上下文
@@ -64,6 +246,82 @@ pub struct ScriptEngine {
     engine: Engine,
 }
 
+/// 项目/组级别的脚本, 对其中的每个请求执行
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SharedScripts {
+    /// 请求前脚本, 在请求自身的请求前脚本之前执行
+    pub pre_request_script: String,
+    /// 响应后脚本, 在请求自身的响应后脚本之前执行
+    pub post_response_script: String,
+}
+
+/// 编译后的一段脚本, Err 为带行列号的编译错误
+#[derive(Debug, Clone)]
+pub struct CompiledScript {
+    pub scope: ScriptScope,
+    pub ast: std::result::Result<Arc<AST>, String>,
+}
+
+impl CompiledScript {
+    /// 错误信息和控制台中使用的名称, 如 `Group pre-request script`
+    pub fn label(&self, phase: ScriptPhase) -> String {
+        match self.scope {
+            ScriptScope::Request => format!("{} script", phase.as_ref()),
+            scope => format!("{} {} script", scope.as_ref(), phase.as_ref().to_lowercase()),
+        }
+    }
+}
+
+/// 请求编译后的前后脚本, 按 项目 → 组 → 请求 的顺序执行, 批量发送时只编译一次
+///
+/// 为空或未启用的脚本不包含在内
+#[derive(Debug, Clone, Default)]
+pub struct CompiledScripts {
+    pub pre_request: Vec<CompiledScript>,
+    pub post_response: Vec<CompiledScript>,
+}
+
+impl CompiledScripts {
+    /// 仅编译请求自身的脚本, 模块从当前目录的 .rhai 文件加载
+    pub fn compile(cfg: &HttpRequestConfig) -> Self {
+        Self::compile_with(&ScriptLibrary::default(), &[], cfg)
+    }
+
+    /// parents 为按顺序在请求脚本之前执行的项目/组脚本, 不受请求的 script_enabled 影响
+    pub fn compile_with(
+        library: &ScriptLibrary,
+        parents: &[(ScriptScope, &SharedScripts)],
+        cfg: &HttpRequestConfig,
+    ) -> Self {
+        let mut scripts = Self::default();
+        let mut add = |scope, pre_request: &str, post_response: &str| {
+            for (list, script) in [
+                (&mut scripts.pre_request, pre_request),
+                (&mut scripts.post_response, post_response),
+            ] {
+                if !script.trim().is_empty() {
+                    list.push(CompiledScript {
+                        scope,
+                        ast: ScriptEngine::shared().compile_with_library(library, script).map(Arc::new),
+                    });
+                }
+            }
+        };
+        for (scope, shared) in parents {
+            add(*scope, &shared.pre_request_script, &shared.post_response_script);
+        }
+        if cfg.script_enabled {
+            add(ScriptScope::Request, &cfg.pre_request_script, &cfg.post_response_script);
+        }
+        scripts
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pre_request.is_empty() && self.post_response.is_empty()
+    }
+}
+
 impl ScriptEngine {
     /// 创建新的脚本引擎实例
     pub fn new() -> Self {
@@ -90,17 +348,55 @@ impl ScriptEngine {
         // 注册网络请求函数
         Self::register_http_functions(&mut engine);
 
+        // 注册 test / expect 函数
+        Self::register_test_functions(&mut engine);
+
+        // import 从项目模块和模块文件中加载
+        engine.set_module_resolver(LibraryResolver);
+
         Self { engine }
     }
 
+    /// 全局共享的脚本引擎
+    ///
+    /// 注册的函数不保存引擎状态 (test() 结果和控制台输出按线程收集), 可在多个任务间复用
+    pub fn shared() -> &'static ScriptEngine {
+        SHARED_ENGINE.get_or_init(ScriptEngine::new)
+    }
+
+    /// 编译脚本, 错误信息带行列号
+    ///
+    /// import 的模块在编译时加载并包含在 AST 中, 模块库见 `compile_with_library`
+    pub fn compile(&self, script: &str) -> std::result::Result<AST, String> {
+        self.engine
+            .compile_into_self_contained(&Scope::new(), script)
+            .map_err(error_with_position)
+    }
+
+    /// 使用指定的模块库编译脚本
+    pub fn compile_with_library(&self, library: &ScriptLibrary, script: &str) -> std::result::Result<AST, String> {
+        LIBRARY.with(|l| *l.borrow_mut() = Some(library.clone()));
+        let ast = self.compile(script);
+        LIBRARY.with(|l| *l.borrow_mut() = None);
+        ast
+    }
+
+    /// 在阻塞线程池中执行请求前脚本, 脚本等待 HTTP 请求时不会占用调用方的异步任务
+    pub async fn run_pre_request(&'static self, ast: Arc<AST>, context: PreRequestContext) -> Result<ScriptResult> {
+        spawn_script(move || self.execute_pre_request(&ast, context)).await
+    }
+
+    /// 在阻塞线程池中执行响应后脚本
+    pub async fn run_post_response(&'static self, ast: Arc<AST>, context: PostResponseContext) -> Result<ScriptResult> {
+        spawn_script(move || self.execute_post_response(&ast, context)).await
+    }
+
     /// 执行请求前脚本
     pub fn execute_pre_request(
-        &mut self,
-        script: &str,
+        &self,
+        ast: &AST,
         context: PreRequestContext,
     ) -> Result<ScriptResult> {
-        let mut console_output = Vec::new();
-
         // 创建作用域
         let mut scope = Scope::new();
 
@@ -109,7 +405,15 @@ impl ScriptEngine {
         scope.push("vars", Self::hashmap_to_map(&context.variables));
 
         // 执行脚本
-        match self.engine.eval_with_scope::<Dynamic>(&mut scope, script) {
+        TEST_RESULTS.with(|r| r.borrow_mut().clear());
+        CONSOLE_OUTPUT.with(|c| c.borrow_mut().clear());
+        let start = std::time::Instant::now();
+        let result = self.engine.eval_ast_with_scope::<Dynamic>(&mut scope, ast);
+        let tests = TEST_RESULTS.with(|r| r.take());
+        let mut console_output = take_console(ScriptPhase::PreRequest);
+        let duration = start.elapsed();
+
+        match result {
             Ok(_) => {
                 // 从 scope 中提取修改后的值
                 let modified_context = Self::extract_pre_request_context(&scope, context)?;
@@ -119,24 +423,36 @@ impl ScriptEngine {
                     error: None,
                     context: ScriptContext::PreRequest(modified_context),
                     console_output,
+                    tests,
+                    duration,
+                })
+            }
+            Err(e) => {
+                let error = error_with_position(e);
+                console_output.push(ConsoleLine {
+                    scope: ScriptScope::Request,
+                    phase: ScriptPhase::PreRequest,
+                    level: ConsoleLevel::Error,
+                    message: error.clone(),
+                });
+                Ok(ScriptResult {
+                    success: false,
+                    error: Some(error),
+                    context: ScriptContext::PreRequest(context),
+                    console_output,
+                    tests,
+                    duration,
                 })
             }
-            Err(e) => Ok(ScriptResult {
-                success: false,
-                error: Some(e.to_string()),
-                context: ScriptContext::PreRequest(context),
-                console_output,
-            }),
         }
     }
 
     /// 执行响应后脚本
     pub fn execute_post_response(
-        &mut self,
-        script: &str,
+        &self,
+        ast: &AST,
         context: PostResponseContext,
     ) -> Result<ScriptResult> {
-        let mut console_output = Vec::new();
         let mut scope = Scope::new();
 
         // 注册上下文
@@ -149,7 +465,15 @@ impl ScriptEngine {
         scope.push("test_message", "".to_string());
 
         // 执行脚本
-        match self.engine.eval_with_scope::<Dynamic>(&mut scope, script) {
+        TEST_RESULTS.with(|r| r.borrow_mut().clear());
+        CONSOLE_OUTPUT.with(|c| c.borrow_mut().clear());
+        let start = std::time::Instant::now();
+        let result = self.engine.eval_ast_with_scope::<Dynamic>(&mut scope, ast);
+        let tests = TEST_RESULTS.with(|r| r.take());
+        let mut console_output = take_console(ScriptPhase::PostResponse);
+        let duration = start.elapsed();
+
+        match result {
             Ok(_) => {
                 let modified_context = Self::extract_post_response_context(&scope, context)?;
 
@@ -158,14 +482,27 @@ impl ScriptEngine {
                     error: None,
                     context: ScriptContext::PostResponse(modified_context),
                     console_output,
+                    tests,
+                    duration,
+                })
+            }
+            Err(e) => {
+                let error = error_with_position(e);
+                console_output.push(ConsoleLine {
+                    scope: ScriptScope::Request,
+                    phase: ScriptPhase::PostResponse,
+                    level: ConsoleLevel::Error,
+                    message: error.clone(),
+                });
+                Ok(ScriptResult {
+                    success: false,
+                    error: Some(error),
+                    context: ScriptContext::PostResponse(context),
+                    console_output,
+                    tests,
+                    duration,
                 })
             }
-            Err(e) => Ok(ScriptResult {
-                success: false,
-                error: Some(e.to_string()),
-                context: ScriptContext::PostResponse(context),
-                console_output,
-            }),
         }
     }
 
@@ -279,39 +616,39 @@ impl ScriptEngine {
     fn register_console_functions(engine: &mut Engine) {
         // console_log for String
         engine.register_fn("console_log", |msg: &str| {
-            println!("[Script] {}", msg);
+            console_log(msg.to_string());
         });
 
         // console_log for integers
         engine.register_fn("console_log", |msg: i64| {
-            println!("[Script] {}", msg);
+            console_log(msg.to_string());
         });
 
         // console_log for floats
         engine.register_fn("console_log", |msg: f64| {
-            println!("[Script] {}", msg);
+            console_log(msg.to_string());
         });
 
         // console_log for booleans
         engine.register_fn("console_log", |msg: bool| {
-            println!("[Script] {}", msg);
+            console_log(msg.to_string());
         });
 
         // console_log for Map (转为 JSON)
         engine.register_fn("console_log", |map: Map| {
             let json_value = Self::map_to_json_value(&map);
-            println!("[Script] {}", serde_json::to_string_pretty(&json_value).unwrap_or_default());
+            console_log(serde_json::to_string_pretty(&json_value).unwrap_or_default());
         });
 
         // console_log for Dynamic (通用)
         engine.register_fn("console_log", |value: Dynamic| {
             if let Ok(s) = value.clone().into_string() {
-                println!("[Script] {}", s);
+                console_log(s.to_string());
             } else if let Some(map) = value.clone().try_cast::<Map>() {
                 let json_value = Self::map_to_json_value(&map);
-                println!("[Script] {}", serde_json::to_string_pretty(&json_value).unwrap_or_default());
+                console_log(serde_json::to_string_pretty(&json_value).unwrap_or_default());
             } else {
-                println!("[Script] {:?}", value);
+                console_log(format!("{:?}", value));
             }
         });
     }
@@ -319,53 +656,19 @@ impl ScriptEngine {
     // ===== 工具函数 =====
     fn register_utility_functions(engine: &mut Engine) {
         // 生成随机数
-        engine.register_fn("random", || -> i64 {
-            use rand::Rng;
-            rand::thread_rng().gen_range(0..1000000)
-        });
+        engine.register_fn("random", || -> i64 { random_int(0, 1000000) });
 
         // 生成随机字符串
-        engine.register_fn("random_string", |length: i64| -> String {
-            use rand::Rng;
-            const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
-            let mut rng = rand::thread_rng();
-            (0..length)
-                .map(|_| {
-                    let idx = rng.gen_range(0..CHARSET.len());
-                    CHARSET[idx] as char
-                })
-                .collect()
-        });
+        engine.register_fn("random_string", |length: i64| -> String { random_string(length.max(0) as usize) });
 
         // 获取当前时间戳(秒)
-        engine.register_fn("timestamp", || -> i64 {
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs() as i64
-        });
+        engine.register_fn("timestamp", timestamp);
 
         // 获取当前时间戳(毫秒)
-        engine.register_fn("timestamp_ms", || -> i64 {
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as i64
-        });
+        engine.register_fn("timestamp_ms", timestamp_ms);
 
         // UUID v4
-        engine.register_fn("uuid", || -> String {
-            use rand::Rng;
-            let mut rng = rand::thread_rng();
-            format!(
-                "{:08x}-{:04x}-4{:03x}-{:04x}-{:012x}",
-                rng.gen::<u32>(),
-                rng.gen::<u16>(),
-                rng.gen::<u16>() & 0x0fff,
-                (rng.gen::<u16>() & 0x3fff) | 0x8000,
-                rng.gen::<u64>() & 0xffffffffffff
-            )
-        });
+        engine.register_fn("uuid", uuid);
     }
 
     // ===== 文件操作函数 =====
@@ -373,7 +676,7 @@ impl ScriptEngine {
         // 读取文件内容
         engine.register_fn("read_file", |path: &str| -> String {
             std::fs::read_to_string(path).unwrap_or_else(|e| {
-                eprintln!("[Script] 读取文件失败 {}: {}", path, e);
+                console_error(format!("读取文件失败 {}: {}", path, e));
                 String::new()
             })
         });
@@ -383,7 +686,7 @@ impl ScriptEngine {
             // 确保父目录存在
             if let Some(parent) = std::path::Path::new(path).parent() {
                 if let Err(e) = std::fs::create_dir_all(parent) {
-                    eprintln!("[Script] 创建目录失败 {}: {}", parent.display(), e);
+                    console_error(format!("创建目录失败 {}: {}", parent.display(), e));
                     return false;
                 }
             }
@@ -391,7 +694,7 @@ impl ScriptEngine {
             match std::fs::write(path, content) {
                 Ok(_) => true,
                 Err(e) => {
-                    eprintln!("[Script] 写入文件失败 {}: {}", path, e);
+                    console_error(format!("写入文件失败 {}: {}", path, e));
                     false
                 }
             }
@@ -404,7 +707,7 @@ impl ScriptEngine {
             // 确保父目录存在
             if let Some(parent) = std::path::Path::new(path).parent() {
                 if let Err(e) = std::fs::create_dir_all(parent) {
-                    eprintln!("[Script] 创建目录失败 {}: {}", parent.display(), e);
+                    console_error(format!("创建目录失败 {}: {}", parent.display(), e));
                     return false;
                 }
             }
@@ -417,12 +720,12 @@ impl ScriptEngine {
                 Ok(mut file) => match file.write_all(content.as_bytes()) {
                     Ok(_) => true,
                     Err(e) => {
-                        eprintln!("[Script] 追加文件失败 {}: {}", path, e);
+                        console_error(format!("追加文件失败 {}: {}", path, e));
                         false
                     }
                 },
                 Err(e) => {
-                    eprintln!("[Script] 打开文件失败 {}: {}", path, e);
+                    console_error(format!("打开文件失败 {}: {}", path, e));
                     false
                 }
             }
@@ -438,7 +741,7 @@ impl ScriptEngine {
             match std::fs::remove_file(path) {
                 Ok(_) => true,
                 Err(e) => {
-                    eprintln!("[Script] 删除文件失败 {}: {}", path, e);
+                    console_error(format!("删除文件失败 {}: {}", path, e));
                     false
                 }
             }
@@ -451,7 +754,7 @@ impl ScriptEngine {
             match std::fs::read(path) {
                 Ok(bytes) => general_purpose::STANDARD.encode(&bytes),
                 Err(e) => {
-                    eprintln!("[Script] 读取文件失败 {}: {}", path, e);
+                    console_error(format!("读取文件失败 {}: {}", path, e));
                     String::new()
                 }
             }
@@ -464,7 +767,7 @@ impl ScriptEngine {
             // 确保父目录存在
             if let Some(parent) = std::path::Path::new(path).parent() {
                 if let Err(e) = std::fs::create_dir_all(parent) {
-                    eprintln!("[Script] 创建目录失败 {}: {}", parent.display(), e);
+                    console_error(format!("创建目录失败 {}: {}", parent.display(), e));
                     return false;
                 }
             }
@@ -473,12 +776,12 @@ impl ScriptEngine {
                 Ok(bytes) => match std::fs::write(path, bytes) {
                     Ok(_) => true,
                     Err(e) => {
-                        eprintln!("[Script] 写入文件失败 {}: {}", path, e);
+                        console_error(format!("写入文件失败 {}: {}", path, e));
                         false
                     }
                 },
                 Err(e) => {
-                    eprintln!("[Script] Base64解码失败: {}", e);
+                    console_error(format!("Base64解码失败: {}", e));
                     false
                 }
             }
@@ -489,7 +792,7 @@ impl ScriptEngine {
             match std::fs::create_dir_all(path) {
                 Ok(_) => true,
                 Err(e) => {
-                    eprintln!("[Script] 创建目录失败 {}: {}", path, e);
+                    console_error(format!("创建目录失败 {}: {}", path, e));
                     false
                 }
             }
@@ -508,7 +811,7 @@ impl ScriptEngine {
                         .collect()
                 },
                 Err(e) => {
-                    eprintln!("[Script] 读取目录失败 {}: {}", path, e);
+                    console_error(format!("读取目录失败 {}: {}", path, e));
                     Vec::new()
                 }
             }
@@ -517,181 +820,119 @@ impl ScriptEngine {
 
     // ===== HTTP 网络请求函数 =====
     fn register_http_functions(engine: &mut Engine) {
+        // 返回 #{status, headers, body, duration, error}, 失败时 status 为 0, error 为错误信息
+
         // HTTP GET 请求（文本）
-        engine.register_fn("http_get", |url: &str| -> String {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            rt.block_on(async {
-                match reqwest::get(url).await {
-                    Ok(response) => {
-                        match response.text().await {
-                            Ok(text) => text,
-                            Err(e) => {
-                                eprintln!("[Script] 读取响应失败: {}", e);
-                                String::new()
-                            }
-                        }
-                    },
-                    Err(e) => {
-                        eprintln!("[Script] HTTP GET 请求失败 {}: {}", url, e);
-                        String::new()
-                    }
-                }
-            })
+        engine.register_fn("http_get", |url: &str| -> Map {
+            script_http("GET", url, "", &Map::new(), false)
         });
 
-        // HTTP GET 请求（二进制，返回 Base64）
-        engine.register_fn("http_get_bytes", |url: &str| -> String {
-            use base64::{engine::general_purpose, Engine as _};
-
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            rt.block_on(async {
-                match reqwest::get(url).await {
-                    Ok(response) => {
-                        match response.bytes().await {
-                            Ok(bytes) => general_purpose::STANDARD.encode(&bytes),
-                            Err(e) => {
-                                eprintln!("[Script] 读取响应失败: {}", e);
-                                String::new()
-                            }
-                        }
-                    },
-                    Err(e) => {
-                        eprintln!("[Script] HTTP GET 请求失败 {}: {}", url, e);
-                        String::new()
-                    }
-                }
-            })
+        // HTTP GET 请求（二进制，body 为 Base64）
+        engine.register_fn("http_get_bytes", |url: &str| -> Map {
+            script_http("GET", url, "", &Map::new(), true)
         });
 
         // HTTP POST 请求（带 JSON body）
-        engine.register_fn("http_post", |url: &str, body: &str| -> String {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            rt.block_on(async {
-                let client = reqwest::Client::new();
-                match client.post(url)
-                    .header("Content-Type", "application/json")
-                    .body(body.to_string())
-                    .send()
-                    .await
-                {
-                    Ok(response) => {
-                        match response.text().await {
-                            Ok(text) => text,
-                            Err(e) => {
-                                eprintln!("[Script] 读取响应失败: {}", e);
-                                String::new()
-                            }
-                        }
-                    },
-                    Err(e) => {
-                        eprintln!("[Script] HTTP POST 请求失败 {}: {}", url, e);
-                        String::new()
-                    }
-                }
-            })
+        engine.register_fn("http_post", |url: &str, body: &str| -> Map {
+            let mut headers = Map::new();
+            headers.insert("Content-Type".into(), Dynamic::from("application/json".to_string()));
+            script_http("POST", url, body, &headers, false)
         });
 
-        // HTTP 请求（完整版，返回响应对象）
+        // HTTP 请求（完整版）
         engine.register_fn("http_request", |url: &str, method: &str, body: &str, headers: Map| -> Map {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            rt.block_on(async {
-                let client = reqwest::Client::new();
-
-                // 构建请求
-                let mut request_builder = match method.to_uppercase().as_str() {
-                    "GET" => client.get(url),
-                    "POST" => client.post(url),
-                    "PUT" => client.put(url),
-                    "DELETE" => client.delete(url),
-                    "PATCH" => client.patch(url),
-                    _ => client.get(url),
-                };
-
-                // 添加请求头
-                for (key, value) in headers.iter() {
-                    if let Ok(value_str) = value.clone().into_string() {
-                        request_builder = request_builder.header(key.to_string(), value_str);
-                    }
-                }
-
-                // 添加请求体
-                if !body.is_empty() && method.to_uppercase() != "GET" {
-                    request_builder = request_builder.body(body.to_string());
-                }
-
-                // 发送请求
-                match request_builder.send().await {
-                    Ok(response) => {
-                        let status = response.status().as_u16() as i64;
-                        let headers_map = {
-                            let mut h = Map::new();
-                            for (name, value) in response.headers() {
-                                if let Ok(v) = value.to_str() {
-                                    h.insert(name.as_str().to_string().into(), Dynamic::from(v.to_string()));
-                                }
-                            }
-                            h
-                        };
-
-                        let body_text = response.text().await.unwrap_or_default();
-
-                        let mut result = Map::new();
-                        result.insert("status".into(), Dynamic::from(status));
-                        result.insert("headers".into(), Dynamic::from(headers_map));
-                        result.insert("body".into(), Dynamic::from(body_text));
-                        result
-                    },
-                    Err(e) => {
-                        eprintln!("[Script] HTTP 请求失败 {}: {}", url, e);
-                        let mut result = Map::new();
-                        result.insert("status".into(), Dynamic::from(0_i64));
-                        result.insert("headers".into(), Dynamic::from(Map::new()));
-                        result.insert("body".into(), Dynamic::from(String::new()));
-                        result.insert("error".into(), Dynamic::from(e.to_string()));
-                        result
-                    }
-                }
-            })
+            script_http(method, url, body, &headers, false)
         });
 
         // 简化的 HTTP 请求（仅 URL 和 method）
         engine.register_fn("http_request", |url: &str, method: &str| -> Map {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            rt.block_on(async {
-                let client = reqwest::Client::new();
-
-                let request_builder = match method.to_uppercase().as_str() {
-                    "GET" => client.get(url),
-                    "POST" => client.post(url),
-                    "PUT" => client.put(url),
-                    "DELETE" => client.delete(url),
-                    "PATCH" => client.patch(url),
-                    _ => client.get(url),
-                };
-
-                match request_builder.send().await {
-                    Ok(response) => {
-                        let status = response.status().as_u16() as i64;
-                        let body_text = response.text().await.unwrap_or_default();
-
-                        let mut result = Map::new();
-                        result.insert("status".into(), Dynamic::from(status));
-                        result.insert("body".into(), Dynamic::from(body_text));
-                        result
-                    },
-                    Err(e) => {
-                        eprintln!("[Script] HTTP 请求失败 {}: {}", url, e);
-                        let mut result = Map::new();
-                        result.insert("status".into(), Dynamic::from(0_i64));
-                        result.insert("body".into(), Dynamic::from(String::new()));
-                        result.insert("error".into(), Dynamic::from(e.to_string()));
-                        result
-                    }
-                }
-            })
+            script_http(method, url, "", &Map::new(), false)
+        });
+    }
+
+    // ===== 测试函数 =====
+    fn register_test_functions(engine: &mut Engine) {
+        engine.register_type_with_name::<Expectation>("Expectation");
+
+        // test("名称", || { ... }), 闭包内抛出的错误记为失败
+        engine.register_fn("test", |ctx: NativeCallContext, name: &str, f: FnPtr| {
+            let result = match f.call_within_context::<Dynamic>(&ctx, ()) {
+                Ok(_) => AssertionResult {
+                    name: name.to_string(),
+                    passed: true,
+                    message: String::new(),
+                },
+                Err(e) => AssertionResult {
+                    name: name.to_string(),
+                    passed: false,
+                    message: Self::error_message(&e),
+                },
+            };
+            TEST_RESULTS.with(|r| r.borrow_mut().push(result));
+        });
+
+        engine.register_fn("expect", |value: Dynamic| Expectation { value });
+
+        // 相等
+        engine.register_fn("to_equal", |e: &mut Expectation, expected: Dynamic| {
+            let actual = Self::dynamic_to_json_value(&e.value);
+            let expected = Self::dynamic_to_json_value(&expected);
+            let equal = match (actual.as_f64(), expected.as_f64()) {
+                (Some(a), Some(b)) => a == b,
+                _ => actual == expected,
+            };
+            Self::expectation(equal, || format!("期望 {} 等于 {}", actual, expected))
+        });
+
+        // 字符串包含子串 / 数组包含元素 / Map 包含 key
+        engine.register_fn("to_contain", |e: &mut Expectation, item: Dynamic| {
+            let actual = Self::dynamic_to_json_value(&e.value);
+            let item = Self::dynamic_to_json_value(&item);
+            let contains = match (&actual, &item) {
+                (serde_json::Value::String(s), serde_json::Value::String(sub)) => s.contains(sub.as_str()),
+                (serde_json::Value::Array(arr), _) => arr.contains(&item),
+                (serde_json::Value::Object(obj), serde_json::Value::String(key)) => obj.contains_key(key),
+                _ => false,
+            };
+            Self::expectation(contains, || format!("期望 {} 包含 {}", actual, item))
+        });
+
+        // 小于
+        engine.register_fn("to_be_below", |e: &mut Expectation, max: Dynamic| {
+            let actual = Self::dynamic_to_json_value(&e.value);
+            let max = Self::dynamic_to_json_value(&max);
+            let below = match (actual.as_f64(), max.as_f64()) {
+                (Some(a), Some(b)) => a < b,
+                _ => false,
+            };
+            Self::expectation(below, || format!("期望 {} 小于 {}", actual, max))
+        });
+
+        // 正则匹配
+        engine.register_fn("to_match", |e: &mut Expectation, pattern: &str| -> Result<(), Box<EvalAltResult>> {
+            let re = regex::Regex::new(pattern).map_err(|err| format!("正则错误: {}", err))?;
+            let actual = e.value.to_string();
+            Self::expectation(re.is_match(&actual), || format!("期望 {:?} 匹配 /{}/", actual, pattern))
         });
     }
 
+    fn expectation(passed: bool, message: impl FnOnce() -> String) -> Result<(), Box<EvalAltResult>> {
+        if passed {
+            Ok(())
+        } else {
+            Err(message().into())
+        }
+    }
+
+    /// 取出最内层的错误信息, 去掉函数调用的包装
+    fn error_message(err: &EvalAltResult) -> String {
+        match err {
+            EvalAltResult::ErrorInFunctionCall(_, _, inner, _) => Self::error_message(inner),
+            EvalAltResult::ErrorRuntime(value, _) => value.to_string(),
+            e => e.to_string(),
+        }
+    }
+
     // ===== 辅助转换函数 =====
     fn pre_request_to_map(context: &PreRequestContext) -> Map {
         let mut map = Map::new();
@@ -879,3 +1120,145 @@ mod urlencoding {
         Ok(std::borrow::Cow::Owned(result))
     }
 }
+
+// ===== 生成器 =====
+// 脚本中的工具函数和 {{$uuid}} 等动态变量共用
+
+/// [min, max) 范围内的随机整数, max <= min 时返回 min
+pub fn random_int(min: i64, max: i64) -> i64 {
+    use rand::Rng;
+    if max <= min {
+        return min;
+    }
+    rand::thread_rng().gen_range(min..max)
+}
+
+/// 由字母和数字组成的随机字符串
+pub fn random_string(length: usize) -> String {
+    use rand::Rng;
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    let mut rng = rand::thread_rng();
+    (0..length)
+        .map(|_| {
+            let idx = rng.gen_range(0..CHARSET.len());
+            CHARSET[idx] as char
+        })
+        .collect()
+}
+
+/// 当前时间戳(秒)
+pub fn timestamp() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+/// 当前时间戳(毫秒)
+pub fn timestamp_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+/// UUID v4
+pub fn uuid() -> String {
+    use rand::Rng;
+    let mut rng = rand::thread_rng();
+    format!(
+        "{:08x}-{:04x}-4{:03x}-{:04x}-{:012x}",
+        rng.gen::<u32>(),
+        rng.gen::<u16>(),
+        rng.gen::<u16>() & 0x0fff,
+        (rng.gen::<u16>() & 0x3fff) | 0x8000,
+        rng.gen::<u64>() & 0xffffffffffff
+    )
+}
+
+/// 在 spawn_blocking 线程中执行脚本, 保留当前请求的 {{$iteration}}
+async fn spawn_script<F>(f: F) -> Result<ScriptResult>
+where
+    F: FnOnce() -> Result<ScriptResult> + Send + 'static,
+{
+    let iteration = crate::util::ITERATION.try_with(|i| *i).unwrap_or(0);
+    tokio::task::spawn_blocking(move || crate::util::ITERATION.sync_scope(iteration, f)).await?
+}
+
+// ===== 脚本 HTTP 请求 =====
+
+/// 脚本中的 HTTP 请求, 使用共享的 HTTP 客户端 (连接池和超时设置与普通请求一致)
+fn script_http(method: &str, url: &str, body: &str, headers: &Map, binary: bool) -> Map {
+    use base64::{engine::general_purpose, Engine as _};
+
+    let start = std::time::Instant::now();
+    let method = method.to_uppercase();
+    let target = url.to_string();
+    let body = body.to_string();
+    let headers: Vec<(String, String)> = headers
+        .iter()
+        .filter_map(|(key, value)| Some((key.to_string(), value.clone().into_string().ok()?)))
+        .collect();
+    let result = block_on(async move {
+        let method = reqwest::Method::from_bytes(method.as_bytes())?;
+        let mut request_builder = crate::get_http_client().request(method.clone(), target);
+        for (key, value) in headers {
+            request_builder = request_builder.header(key, value);
+        }
+        if !body.is_empty() && method != reqwest::Method::GET {
+            request_builder = request_builder.body(body);
+        }
+
+        let response = request_builder.send().await?;
+        let status = response.status().as_u16() as i64;
+        let headers: Vec<(String, String)> = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.as_str().to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let bytes = response.bytes().await?;
+        anyhow::Ok((status, headers, bytes))
+    })
+    .and_then(|result| result);
+
+    let mut map = Map::new();
+    let (status, headers, body, error) = match result {
+        Ok((status, headers, bytes)) => {
+            let headers: Map = headers
+                .into_iter()
+                .map(|(name, value)| (name.into(), Dynamic::from(value)))
+                .collect();
+            let body = if binary {
+                general_purpose::STANDARD.encode(&bytes)
+            } else {
+                String::from_utf8_lossy(&bytes).into_owned()
+            };
+            (status, headers, body, String::new())
+        }
+        Err(e) => {
+            let error = crate::error_kind::message(&e);
+            console_error(format!("HTTP 请求失败 {}: {}", url, error));
+            (0, Map::new(), String::new(), error)
+        }
+    };
+    map.insert("status".into(), Dynamic::from(status));
+    map.insert("headers".into(), Dynamic::from(headers));
+    map.insert("body".into(), Dynamic::from(body));
+    map.insert("duration".into(), Dynamic::from(start.elapsed().as_millis() as i64));
+    map.insert("error".into(), Dynamic::from(error));
+    map
+}
+
+/// 在同步的脚本函数中等待 future
+///
+/// 脚本在 spawn_blocking 线程中执行 (见 `spawn_script`), future 交给当前运行时执行,
+/// 这里只阻塞脚本所在的线程, 不占用运行时的工作线程
+fn block_on<Fut>(fut: Fut) -> anyhow::Result<Fut::Output>
+where
+    Fut: std::future::Future + Send + 'static,
+    Fut::Output: Send + 'static,
+{
+    let handle = tokio::runtime::Handle::try_current()
+        .map_err(|_| anyhow::anyhow!("脚本 HTTP 请求需要在 tokio 运行时中执行"))?;
+    Ok(futures::executor::block_on(handle.spawn(fut))?)
+}
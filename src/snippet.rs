@@ -0,0 +1,356 @@
+//! 将请求导出为 curl 命令和各语言代码片段
+
+use anyhow::{bail, Result};
+
+use crate::util::{self, FormDataValue};
+use crate::{
+    HttpRequestConfig, Method, PairUi, RequestBodyRawType, RequestBodyTab,
+    APPLICATION_FORM, APPLICATION_JSON, APPLICATION_STREAM, CONTENT_TYPE, TEXT_PLAIN, TEXT_XML,
+};
+
+#[derive(Debug, Default, strum::AsRefStr, Clone, PartialEq)]
+pub enum SnippetLang {
+    #[default]
+    #[strum(serialize = "cURL")]
+    Curl,
+    #[strum(serialize = "Rust reqwest")]
+    RustReqwest,
+    #[strum(serialize = "Python requests")]
+    PythonRequests,
+    #[strum(serialize = "JavaScript fetch")]
+    JavaScriptFetch,
+}
+
+/// 替换变量后的请求
+pub(crate) struct ResolvedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Body,
+}
+
+pub(crate) enum Body {
+    None,
+    Raw(String),
+    Form(Vec<(String, String)>),
+    Multipart(Vec<Part>),
+    File(String),
+}
+
+pub(crate) enum Part {
+    Text(String, String),
+    File(String, String),
+}
+
+/// 生成代码片段, 变量按 vars 替换
+pub fn generate(cfg: &HttpRequestConfig, vars: &Vec<PairUi>, lang: &SnippetLang) -> Result<String> {
+    let req = resolve(cfg, vars)?;
+    Ok(match lang {
+        SnippetLang::Curl => to_curl(&req),
+        SnippetLang::RustReqwest => to_rust(&req),
+        SnippetLang::PythonRequests => to_python(&req),
+        SnippetLang::JavaScriptFetch => to_javascript(&req),
+    })
+}
+
+pub(crate) fn resolve(cfg: &HttpRequestConfig, vars: &Vec<PairUi>) -> Result<ResolvedRequest> {
+    if cfg.method == Method::WS {
+        bail!("WebSocket 请求不支持导出");
+    }
+
+    let mut url = util::parse_var_str(&cfg.url, vars);
+    let query = util::real_tuple_vec(&cfg.query, vars);
+    if !query.is_empty() {
+        let encoded = query
+            .iter()
+            .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+            .collect::<Vec<_>>()
+            .join("&");
+        url.push(if url.contains('?') { '&' } else { '?' });
+        url.push_str(&encoded);
+    }
+
+    let mut headers = util::real_tuple_vec(&cfg.header, vars);
+    let has_content_type = headers.iter().any(|(k, _)| k.eq_ignore_ascii_case(CONTENT_TYPE));
+    let mut default_content_type = |content_type: &str| {
+        if !has_content_type {
+            headers.push((CONTENT_TYPE.to_owned(), content_type.to_owned()));
+        }
+    };
+
+    // 与 HttpRequestConfig::request_build 保持一致
+    let body = match cfg.body_tab_ui {
+        RequestBodyTab::Raw if cfg.body_raw.is_empty() => Body::None,
+        RequestBodyTab::Raw => match cfg.body_raw_type {
+            RequestBodyRawType::BinaryFile => {
+                default_content_type(APPLICATION_STREAM);
+                Body::File(cfg.real_body_raw(vars))
+            }
+            ref raw_type => {
+                default_content_type(match raw_type {
+                    RequestBodyRawType::Json => APPLICATION_JSON,
+                    RequestBodyRawType::Form => APPLICATION_FORM,
+                    RequestBodyRawType::XML => TEXT_XML,
+                    _ => TEXT_PLAIN,
+                });
+                Body::Raw(cfg.real_body_raw(vars))
+            }
+        },
+        RequestBodyTab::Form => Body::Form(util::real_tuple_vec(&cfg.body_form, vars)),
+        RequestBodyTab::FormData => {
+            let mut parts = Vec::new();
+            for (k, v) in util::real_form_data(&cfg.body_form_data, vars) {
+                match v {
+                    FormDataValue::Files(paths) => {
+                        for path in paths {
+                            parts.push(Part::File(k.to_owned(), path));
+                        }
+                    }
+                    FormDataValue::Text(text) => parts.push(Part::Text(k, text)),
+                }
+            }
+            Body::Multipart(parts)
+        }
+    };
+
+    if matches!(body, Body::Form(_) | Body::Multipart(_)) {
+        // 由客户端设置 (multipart 需要 boundary)
+        headers.retain(|(k, _)| !k.eq_ignore_ascii_case(CONTENT_TYPE));
+    }
+
+    Ok(ResolvedRequest {
+        method: cfg.method.as_ref().to_owned(),
+        url,
+        headers,
+        body,
+    })
+}
+
+fn to_curl(req: &ResolvedRequest) -> String {
+    let mut lines = vec![format!("curl {}", shell_quote(&req.url))];
+
+    match req.method.as_str() {
+        "GET" if matches!(req.body, Body::None) => {}
+        "HEAD" => lines.push("--head".to_owned()),
+        method => lines.push(format!("-X {}", method)),
+    }
+
+    for (k, v) in &req.headers {
+        lines.push(format!("-H {}", shell_quote(&format!("{}: {}", k, v))));
+    }
+
+    match &req.body {
+        Body::None => {}
+        Body::Raw(text) => lines.push(format!("--data-raw {}", shell_quote(text))),
+        Body::File(path) => lines.push(format!("--data-binary {}", shell_quote(&format!("@{}", path)))),
+        Body::Form(pairs) => {
+            for (k, v) in pairs {
+                lines.push(format!("--data-urlencode {}", shell_quote(&format!("{}={}", k, v))));
+            }
+        }
+        Body::Multipart(parts) => {
+            for part in parts {
+                lines.push(match part {
+                    Part::Text(k, v) => format!("--form-string {}", shell_quote(&format!("{}={}", k, v))),
+                    Part::File(k, path) => format!("-F {}", shell_quote(&format!("{}=@{}", k, path))),
+                });
+            }
+        }
+    }
+
+    lines.join(" \\\n  ")
+}
+
+fn to_rust(req: &ResolvedRequest) -> String {
+    let mut out = String::new();
+
+    if let Body::Multipart(parts) = &req.body {
+        out.push_str("let form = reqwest::multipart::Form::new()");
+        for part in parts {
+            match part {
+                Part::Text(k, v) => out.push_str(&format!("\n    .text({:?}, {:?})", k, v)),
+                Part::File(k, path) => out.push_str(&format!(
+                    "\n    .part({:?}, reqwest::multipart::Part::bytes(std::fs::read({:?})?).file_name({:?}))",
+                    k,
+                    path,
+                    file_name(path)
+                )),
+            }
+        }
+        out.push_str(";\n\n");
+    }
+
+    out.push_str("let client = reqwest::Client::new();\n");
+    out.push_str("let response = client\n");
+    out.push_str(&format!(
+        "    .request(reqwest::Method::{}, {:?})\n",
+        req.method, req.url
+    ));
+    for (k, v) in &req.headers {
+        out.push_str(&format!("    .header({:?}, {:?})\n", k, v));
+    }
+    match &req.body {
+        Body::None => {}
+        Body::Raw(text) => out.push_str(&format!("    .body({:?})\n", text)),
+        Body::File(path) => out.push_str(&format!("    .body(std::fs::read({:?})?)\n", path)),
+        Body::Form(pairs) => {
+            let pairs = pairs
+                .iter()
+                .map(|(k, v)| format!("({:?}, {:?})", k, v))
+                .collect::<Vec<_>>()
+                .join(", ");
+            out.push_str(&format!("    .form(&[{}])\n", pairs));
+        }
+        Body::Multipart(_) => out.push_str("    .multipart(form)\n"),
+    }
+    out.push_str("    .send()\n    .await?;\n\n");
+    out.push_str("println!(\"{}\", response.status());\n");
+    out.push_str("println!(\"{}\", response.text().await?);\n");
+    out
+}
+
+fn to_python(req: &ResolvedRequest) -> String {
+    let mut out = String::from("import requests\n\n");
+    out.push_str(&format!("url = {}\n", quote(&req.url)));
+
+    out.push_str("headers = {");
+    if !req.headers.is_empty() {
+        out.push('\n');
+        for (k, v) in &req.headers {
+            out.push_str(&format!("    {}: {},\n", quote(k), quote(v)));
+        }
+    }
+    out.push_str("}\n");
+
+    let mut args = vec!["headers=headers".to_owned()];
+    match &req.body {
+        Body::None => {}
+        Body::Raw(text) => {
+            out.push_str(&format!("data = {}\n", quote(text)));
+            args.push("data=data".to_owned());
+        }
+        Body::File(path) => {
+            out.push_str(&format!("data = open({}, \"rb\")\n", quote(path)));
+            args.push("data=data".to_owned());
+        }
+        Body::Form(pairs) => {
+            out.push_str(&format!("data = {}\n", python_pairs(pairs.iter().map(|(k, v)| (k, quote(v))))));
+            args.push("data=data".to_owned());
+        }
+        Body::Multipart(parts) => {
+            let texts = parts.iter().filter_map(|p| match p {
+                Part::Text(k, v) => Some((k, quote(v))),
+                _ => None,
+            });
+            let files = parts.iter().filter_map(|p| match p {
+                Part::File(k, path) => Some((k, format!("open({}, \"rb\")", quote(path)))),
+                _ => None,
+            });
+            out.push_str(&format!("data = {}\n", python_pairs(texts)));
+            out.push_str(&format!("files = {}\n", python_pairs(files)));
+            args.push("data=data".to_owned());
+            args.push("files=files".to_owned());
+        }
+    }
+
+    out.push_str(&format!(
+        "\nresponse = requests.request({}, url, {})\n",
+        quote(&req.method),
+        args.join(", ")
+    ));
+    out.push_str("print(response.status_code)\nprint(response.text)\n");
+    out
+}
+
+fn to_javascript(req: &ResolvedRequest) -> String {
+    let mut out = String::new();
+    let uses_file = match &req.body {
+        Body::File(_) => true,
+        Body::Multipart(parts) => parts.iter().any(|p| matches!(p, Part::File(..))),
+        _ => false,
+    };
+    if uses_file {
+        out.push_str("import fs from \"node:fs\";\n\n");
+    }
+
+    let body = match &req.body {
+        Body::None => None,
+        Body::Raw(text) => Some(quote(text)),
+        Body::File(path) => Some(format!("fs.readFileSync({})", quote(path))),
+        Body::Form(pairs) => {
+            let pairs = pairs
+                .iter()
+                .map(|(k, v)| format!("[{}, {}]", quote(k), quote(v)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            Some(format!("new URLSearchParams([{}])", pairs))
+        }
+        Body::Multipart(parts) => {
+            out.push_str("const form = new FormData();\n");
+            for part in parts {
+                match part {
+                    Part::Text(k, v) => out.push_str(&format!("form.append({}, {});\n", quote(k), quote(v))),
+                    Part::File(k, path) => out.push_str(&format!(
+                        "form.append({}, new Blob([fs.readFileSync({})]), {});\n",
+                        quote(k),
+                        quote(path),
+                        quote(&file_name(path))
+                    )),
+                }
+            }
+            out.push('\n');
+            Some("form".to_owned())
+        }
+    };
+
+    out.push_str(&format!("const response = await fetch({}, {{\n", quote(&req.url)));
+    out.push_str(&format!("  method: {},\n", quote(&req.method)));
+    out.push_str("  headers: {");
+    if !req.headers.is_empty() {
+        out.push('\n');
+        for (k, v) in &req.headers {
+            out.push_str(&format!("    {}: {},\n", quote(k), quote(v)));
+        }
+        out.push_str("  ");
+    }
+    out.push_str("},\n");
+    if let Some(body) = body {
+        out.push_str(&format!("  body: {},\n", body));
+    }
+    out.push_str("});\n\n");
+    out.push_str("console.log(response.status);\nconsole.log(await response.text());\n");
+    out
+}
+
+fn python_pairs<'a>(pairs: impl Iterator<Item = (&'a String, String)>) -> String {
+    let pairs = pairs
+        .map(|(k, v)| format!("({}, {})", quote(k), v))
+        .collect::<Vec<_>>();
+    format!("[{}]", pairs.join(", "))
+}
+
+/// 双引号字符串, Python 和 JavaScript 通用
+fn quote(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_default()
+}
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+fn file_name(path: &str) -> String {
+    std::path::Path::new(path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(path)
+        .to_owned()
+}
+
+pub(crate) fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
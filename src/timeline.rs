@@ -0,0 +1,82 @@
+//! 批量发送过程中的每秒统计
+
+use std::time::Duration;
+
+use crate::histogram::LatencyHistogram;
+
+/// 每秒统计的响应时间百分位数
+pub const TIMELINE_PERCENTILES: [f64; 3] = [50.0, 95.0, 99.0];
+
+/// 一秒内完成的请求
+#[derive(Debug, Clone, Default)]
+pub struct TimelineBucket {
+    pub requests: u64,
+    pub errors: u64,
+    pub upload_bytes: u64,
+    pub download_bytes: u64,
+    /// TIMELINE_PERCENTILES 对应的响应时间 (毫秒), 该秒结束后计算
+    pub latency: [Option<f64>; 3],
+}
+
+/// 按完成时间分桶, 只保留当前一秒的直方图, 内存随秒数线性增长
+#[derive(Debug, Clone, Default)]
+pub struct Timeline {
+    buckets: Vec<TimelineBucket>,
+    current: LatencyHistogram,
+}
+
+impl Timeline {
+    /// 记录一个响应, elapsed 为从批量发送开始到响应完成的时间
+    pub fn record_response(&mut self, elapsed: Duration, latency: Duration, upload: u64, download: u64, error: bool) {
+        let bucket = self.bucket_mut(elapsed);
+        bucket.requests += 1;
+        bucket.upload_bytes += upload;
+        bucket.download_bytes += download;
+        if error {
+            bucket.errors += 1;
+        }
+        self.current.record(latency);
+    }
+
+    /// 记录一个请求错误
+    pub fn record_error(&mut self, elapsed: Duration) {
+        let bucket = self.bucket_mut(elapsed);
+        bucket.requests += 1;
+        bucket.errors += 1;
+    }
+
+    fn bucket_mut(&mut self, elapsed: Duration) -> &mut TimelineBucket {
+        let second = elapsed.as_secs() as usize;
+        if second >= self.buckets.len() {
+            // 进入新的一秒, 结束上一秒的统计
+            if let Some(last) = self.buckets.last_mut() {
+                last.latency = TIMELINE_PERCENTILES.map(|p| self.current.percentile_ms(p));
+            }
+            self.current.reset();
+            self.buckets.resize_with(second + 1, Default::default);
+        }
+        // 响应按完成顺序到达, 不会落入已结束的秒
+        self.buckets.last_mut().unwrap()
+    }
+
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    pub fn buckets(&self) -> &[TimelineBucket] {
+        &self.buckets
+    }
+
+    /// 第 i 秒的响应时间百分位数, 当前一秒实时计算
+    pub fn latency(&self, i: usize) -> [Option<f64>; 3] {
+        if i + 1 == self.buckets.len() {
+            TIMELINE_PERCENTILES.map(|p| self.current.percentile_ms(p))
+        } else {
+            self.buckets.get(i).map(|b| b.latency).unwrap_or_default()
+        }
+    }
+}
@@ -2,7 +2,7 @@
 
 use std::{ffi::OsStr, path::Path};
 
-use crate::{HttpRequestConfig, HttpResponse};
+use crate::{HttpRequestConfig, HttpResponse, SentRequest};
 use anyhow::{bail, Result};
 use eframe::egui;
 use image::GenericImageView;
@@ -11,7 +11,10 @@ use lazy_static::lazy_static;
 use regex::Regex;
 
 use crate::{AppConfig, PairUi, Project};
-use crate::script_engine::{ScriptEngine, PreRequestContext, PostResponseContext, ScriptContext};
+use crate::script_engine::{
+    CompiledScripts, ConsoleLevel, ConsoleLine, PostResponseContext, PreRequestContext, ScriptContext, ScriptEngine,
+    ScriptPhase,
+};
 
 pub fn load_app_icon() -> eframe::egui::IconData {
     let app_icon_bytes = include_bytes!("../data/icon.jpg");
@@ -92,7 +95,8 @@ pub fn load_project(project_path: &str) -> Result<Project> {
         bail!("文件不存在")
     }
     let data = std::fs::read(&load_path)?;
-    let dat: Project = serde_json::from_slice(data.as_slice())?;
+    let mut dat: Project = serde_json::from_slice(data.as_slice())?;
+    dat.dir = load_path.parent().map(Path::to_path_buf);
 
     Ok(dat)
 }
@@ -149,7 +153,37 @@ pub async fn read_binary(path: &str) -> Result<Vec<u8>> {
     })
 }
 
-pub async fn handle_multipart(kv_vec: &Vec<(String, String)>) -> Result<reqwest::multipart::Form> {
+/// multipart 字段的值
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormDataValue {
+    Text(String),
+    /// `@a.jpg @b.jpg` 形式的文件路径
+    Files(Vec<String>),
+}
+
+/// 使用变量填充 multipart 字段
+///
+/// 是否为文件按替换前的值判断, 避免变量值中的 @ (如邮箱) 被当作文件路径
+pub fn real_form_data(vec: &Vec<PairUi>, vars: &Vec<PairUi>) -> Vec<(String, FormDataValue)> {
+    tuple_vec(vec)
+        .into_iter()
+        .map(|(k, v)| {
+            let value = if !v.is_empty() && v.contains('@') {
+                FormDataValue::Files(
+                    v.split('@')
+                        .map(|e| parse_var_str(e.trim(), vars))
+                        .filter(|e| !e.is_empty())
+                        .collect(),
+                )
+            } else {
+                FormDataValue::Text(parse_var_str(v, vars))
+            };
+            (parse_var_str(k, vars), value)
+        })
+        .collect()
+}
+
+pub async fn handle_multipart(kv_vec: &Vec<(String, FormDataValue)>) -> Result<reqwest::multipart::Form> {
     use reqwest::multipart::{Form, Part};
 
     let mut form = Form::new();
@@ -157,22 +191,20 @@ pub async fn handle_multipart(kv_vec: &Vec<(String, String)>) -> Result<reqwest:
     // file : @a.jpg
     // files: @a.jpg @b.jpg
     for (k, v) in kv_vec {
-        if !v.is_empty() && v.contains('@') {
-            let filepaths: Vec<_> = v
-                .split('@')
-                .filter(|e| !e.is_empty())
-                .map(|e| e.trim())
-                .collect();
-            for filepath in filepaths {
-                let file_body = read_binary(filepath).await?;
-
-                form = form.part(
-                    k.to_owned(),
-                    Part::bytes(file_body).file_name(get_filename(filepath)?),
-                );
+        match v {
+            FormDataValue::Files(filepaths) => {
+                for filepath in filepaths {
+                    let file_body = read_binary(filepath).await?;
+
+                    form = form.part(
+                        k.to_owned(),
+                        Part::bytes(file_body).file_name(get_filename(filepath)?),
+                    );
+                }
+            }
+            FormDataValue::Text(text) => {
+                form = form.text(k.to_owned(), text.to_owned());
             }
-        } else {
-            form = form.text(k.to_owned(), v.to_owned());
         }
     }
     Ok(form)
@@ -210,6 +242,15 @@ pub fn save_project(dir: &str, project: &Project) -> Result<()> {
 }
 
 pub async fn http_send(req_cfg: &HttpRequestConfig, vars: &Vec<PairUi>) -> Result<HttpResponse> {
+    http_send_with_scripts(req_cfg, vars, &CompiledScripts::compile(req_cfg)).await
+}
+
+/// 使用预先编译的脚本发送请求, 批量发送时避免每个请求重新编译
+pub async fn http_send_with_scripts(
+    req_cfg: &HttpRequestConfig,
+    vars: &Vec<PairUi>,
+    scripts: &CompiledScripts,
+) -> Result<HttpResponse> {
     let mut request_size = 0u64;
     request_size += req_cfg.url.len() as u64;
     request_size += req_cfg.body_raw.len() as u64;
@@ -222,10 +263,15 @@ pub async fn http_send(req_cfg: &HttpRequestConfig, vars: &Vec<PairUi>) -> Resul
     // 创建可变的请求配置副本用于脚本修改
     let mut modified_req_cfg = req_cfg.clone();
     let mut script_vars = vars.clone();
+    let mut script_tests = Vec::new();
+    let mut script_errors = Vec::new();
+    let mut console = Vec::new();
+    let mut pre_script_time = None;
+    let mut post_script_time = None;
 
     // 执行 Pre-Request Script
-    if req_cfg.script_enabled && !req_cfg.pre_request_script.trim().is_empty() {
-        let mut engine = ScriptEngine::new();
+    for script in &scripts.pre_request {
+        let label = script.label(ScriptPhase::PreRequest);
 
         let context = PreRequestContext {
             url: modified_req_cfg.url.clone(),
@@ -244,8 +290,18 @@ pub async fn http_send(req_cfg: &HttpRequestConfig, vars: &Vec<PairUi>) -> Resul
                 .collect(),
         };
 
-        match engine.execute_pre_request(&req_cfg.pre_request_script, context) {
+        let result = match &script.ast {
+            Ok(ast) => ScriptEngine::shared().run_pre_request(ast.clone(), context).await,
+            Err(err) => Err(anyhow::anyhow!("{}", err)),
+        };
+        match result {
             Ok(result) => {
+                script_tests.extend(result.tests);
+                console.extend(result.console_output.into_iter().map(|line| ConsoleLine {
+                    scope: script.scope,
+                    ..line
+                }));
+                *pre_script_time.get_or_insert_with(Default::default) += result.duration;
                 if result.success {
                     // 应用脚本修改
                     if let ScriptContext::PreRequest(ctx) = result.context {
@@ -292,23 +348,35 @@ pub async fn http_send(req_cfg: &HttpRequestConfig, vars: &Vec<PairUi>) -> Resul
                         }
                     }
                 } else if let Some(err) = result.error {
-                    eprintln!("Pre-request script error: {}", err);
+                    script_errors.push(format!("{}: {}", label, err));
                 }
             }
             Err(e) => {
-                eprintln!("Pre-request script execution error: {}", e);
+                script_errors.push(format!("{}: {}", label, e));
+                console.push(ConsoleLine {
+                    scope: script.scope,
+                    phase: ScriptPhase::PreRequest,
+                    level: ConsoleLevel::Error,
+                    message: e.to_string(),
+                });
             }
         }
     }
 
-    let request_builder = modified_req_cfg.request_build(&script_vars).await?;
+    let (client, request) = modified_req_cfg.request_build(&script_vars).await?.build_split();
+    let request = request?;
+    let sent_request = SentRequest::from_request(&request);
+    let started_at = std::time::SystemTime::now();
     let start_time = std::time::Instant::now();
-    let response = request_builder.send().await?;
-    let duration = start_time.elapsed().as_millis();
+    let response = client.execute(request).await?;
+    let latency = start_time.elapsed();
+    let duration = latency.as_millis();
     let status = response.status();
     let version = response.version();
     let headers = response.headers().to_owned();
-    let data_vec = response.bytes().await.and_then(|bs| Ok(bs.to_vec())).ok();
+    let receive_start = std::time::Instant::now();
+    let data_vec = Some(response.bytes().await?.to_vec());
+    let receive_time = receive_start.elapsed();
 
     let response_size = data_vec.as_ref().map(|v| v.len() as u64).unwrap_or(0);
 
@@ -324,8 +392,8 @@ pub async fn http_send(req_cfg: &HttpRequestConfig, vars: &Vec<PairUi>) -> Resul
         .unwrap_or_default();
 
     // 执行 Post-Response Script
-    if req_cfg.script_enabled && !req_cfg.post_response_script.trim().is_empty() {
-        let mut engine = ScriptEngine::new();
+    for script in &scripts.post_response {
+        let label = script.label(ScriptPhase::PostResponse);
 
         let request_context = PreRequestContext {
             url: modified_req_cfg.url.clone(),
@@ -357,8 +425,18 @@ pub async fn http_send(req_cfg: &HttpRequestConfig, vars: &Vec<PairUi>) -> Resul
                 .collect(),
         };
 
-        match engine.execute_post_response(&req_cfg.post_response_script, context) {
+        let result = match &script.ast {
+            Ok(ast) => ScriptEngine::shared().run_post_response(ast.clone(), context).await,
+            Err(err) => Err(anyhow::anyhow!("{}", err)),
+        };
+        match result {
             Ok(result) => {
+                script_tests.extend(result.tests);
+                console.extend(result.console_output.into_iter().map(|line| ConsoleLine {
+                    scope: script.scope,
+                    ..line
+                }));
+                *post_script_time.get_or_insert_with(Default::default) += result.duration;
                 if result.success {
                     // 应用变量修改（post-response 主要用于修改变量）
                     if let ScriptContext::PostResponse(ctx) = result.context {
@@ -375,39 +453,83 @@ pub async fn http_send(req_cfg: &HttpRequestConfig, vars: &Vec<PairUi>) -> Resul
                         }
                     }
                 } else if let Some(err) = result.error {
-                    eprintln!("Post-response script error: {}", err);
+                    script_errors.push(format!("{}: {}", label, err));
                 }
             }
             Err(e) => {
-                eprintln!("Post-response script execution error: {}", e);
+                script_errors.push(format!("{}: {}", label, e));
+                console.push(ConsoleLine {
+                    scope: script.scope,
+                    phase: ScriptPhase::PostResponse,
+                    level: ConsoleLevel::Error,
+                    message: e.to_string(),
+                });
             }
         }
     }
 
-    // 检查变量是否被脚本修改过
-    let modified_vars = if req_cfg.script_enabled &&
-                          (!req_cfg.pre_request_script.trim().is_empty() ||
-                           !req_cfg.post_response_script.trim().is_empty()) {
-        Some(script_vars)
-    } else {
-        None
-    };
+    // 只返回脚本新增或修改过值的变量
+    let modified_vars = Some(changed_vars(vars, script_vars)).filter(|changed| !changed.is_empty());
 
     Ok(HttpResponse {
         data_vec,
         headers,
         version,
         status,
+        started_at,
         img: None,
         text: None,
         headers_str,
         duration,
+        latency,
         request_size,
         response_size,
+        request: sent_request,
+        receive_time,
         modified_vars,
+        assertion_results: Vec::new(),
+        script_tests,
+        script_errors,
+        console,
+        pre_script_time,
+        post_script_time,
     })
 }
 
+/// 格式化为 ISO 8601 UTC 时间, 如 `2024-01-02T03:04:05.678Z`
+pub fn iso_timestamp(time: std::time::SystemTime) -> String {
+    let since_epoch = time.duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (hour, minute, second) = ((secs / 3600) % 24, (secs / 60) % 60, secs % 60);
+
+    // 按公历从天数计算年月日
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        since_epoch.subsec_millis()
+    )
+}
+
+/**
+ * 替换字符串中的 {{var}}
+ *
+ * 按 vars 的顺序取第一个启用的同名变量, 调用方应按优先级排列 (见 Project::resolved_variables)
+ */
 pub fn parse_var_str(oragin_str: &str, vars: &Vec<PairUi>) -> String {
     lazy_static! {
         // {var}}       to var
@@ -421,7 +543,10 @@ pub fn parse_var_str(oragin_str: &str, vars: &Vec<PairUi>) -> String {
             let from = &cap[0];
             let var_name = &cap[1].trim();
 
-            match vars.iter().find(|e| e.key.eq(var_name)) {
+            if let Some(value) = dynamic_var(var_name) {
+                return value;
+            }
+            match vars.iter().find(|e| !e.disable && e.key.eq(var_name)) {
                 Some(res) => cap[0].replace(from, &res.value),
                 None => from.to_owned(),
             }
@@ -430,6 +555,79 @@ pub fn parse_var_str(oragin_str: &str, vars: &Vec<PairUi>) -> String {
     r2
 }
 
+tokio::task_local! {
+    /// 批量发送/数据驱动运行中当前请求的序号 (从 0 开始), 供 {{$iteration}} 使用
+    pub static ITERATION: usize;
+}
+
+/**
+ * 内置动态变量, 每次替换都重新生成
+ *
+ * - {{$uuid}}
+ * - {{$timestamp}} / {{$timestamp_ms}}
+ * - {{$isoTimestamp}}
+ * - {{$randomInt}} / {{$randomInt min max}}, 默认 [0, 1000000)
+ * - {{$randomString}} / {{$randomString len}}, 默认长度 16
+ * - {{$iteration}}, 不在批量发送中时为 0
+ */
+fn dynamic_var(name: &str) -> Option<String> {
+    use crate::script_engine as gen;
+
+    let mut args = name.strip_prefix('$')?.split_whitespace();
+    let value = match args.next()? {
+        "uuid" => gen::uuid(),
+        "timestamp" => gen::timestamp().to_string(),
+        "timestamp_ms" => gen::timestamp_ms().to_string(),
+        "isoTimestamp" => iso_timestamp(std::time::SystemTime::now()),
+        "randomInt" => {
+            let min = args.next().and_then(|v| v.parse().ok()).unwrap_or(0);
+            let max = args.next().and_then(|v| v.parse().ok()).unwrap_or(1000000);
+            gen::random_int(min, max).to_string()
+        }
+        "randomString" => {
+            let len = args.next().and_then(|v| v.parse().ok()).unwrap_or(16);
+            gen::random_string(len)
+        }
+        "iteration" => ITERATION.try_with(|i| *i).unwrap_or(0).to_string(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/**
+ * 脚本执行后新增或值被修改的变量
+ *
+ * after 由 before 复制而来, 脚本只会原地修改或在末尾追加, 因此按下标比较
+ */
+fn changed_vars(before: &[PairUi], after: Vec<PairUi>) -> Vec<PairUi> {
+    after
+        .into_iter()
+        .enumerate()
+        .filter(|(i, var)| before.get(*i).map_or(true, |old| old.value != var.value))
+        .map(|(_, var)| var)
+        .collect()
+}
+
+/**
+ * 去掉只来自数据行的变量, 避免数据文件的列被写回项目
+ */
+pub fn drop_row_vars(modified: &mut Vec<PairUi>, row: &[PairUi], base: &[PairUi]) {
+    modified.retain(|var| !row.iter().any(|r| r.key == var.key) || base.iter().any(|b| b.key == var.key));
+}
+
+/**
+ * 将脚本修改后的变量合并到目标变量列表中
+ */
+pub fn merge_vars(target: &mut Vec<PairUi>, modified: &[PairUi]) {
+    for var in modified {
+        if let Some(existing) = target.iter_mut().find(|v| v.key == var.key) {
+            existing.value = var.value.clone();
+        } else {
+            target.push(var.clone());
+        }
+    }
+}
+
 pub fn real_tuple_fn((k, v): &(&str, &str), vars: &Vec<PairUi>) -> (String, String) {
     (parse_var_str(k, vars), parse_var_str(v, vars))
 }
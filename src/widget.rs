@@ -1,7 +1,12 @@
 use core::f32;
 
-use crate::COLUMN_WIDTH_INITIAL;
-use api_test_rs::PairUi;
+use crate::{ASSERTION_KINDS, COLUMN_WIDTH_INITIAL, PROFILE_KINDS};
+use api_test_rs::assertion::{Assertion, AssertionKind, AssertionResult};
+use api_test_rs::load::{self, LoadProfile, LoadStage, ProfileKind};
+use api_test_rs::report::MetricDiff;
+use api_test_rs::script_engine::{ConsoleLevel, ConsoleLine, ScriptScope, SharedScripts};
+use api_test_rs::timeline::{Timeline, TimelineBucket};
+use api_test_rs::{IterationResult, PairUi, RequestStats};
 use eframe::{
     egui::{self, Response, RichText, Ui},
     epaint::Color32,
@@ -99,6 +104,689 @@ pub fn pair_table(ui: &mut Ui, id: impl std::hash::Hash, pair_vec: &mut Vec<Pair
         });
 }
 
+/// 变量表格, 勾选表示启用
+pub fn variable_table(ui: &mut Ui, id: impl std::hash::Hash, vars: &mut Vec<PairUi>) {
+    egui_extras::TableBuilder::new(ui)
+        .id_salt(id)
+        .striped(true)
+        .resizable(true)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(egui_extras::Column::auto())
+        .column(egui_extras::Column::auto())
+        .column(egui_extras::Column::auto().range(100.0..=400.0))
+        .column(egui_extras::Column::auto())
+        .min_scrolled_height(10.0)
+        .header(20.0, |mut header| {
+            header.col(|ui| {
+                ui.label("启用").on_hover_text("勾选以启用该变量");
+            });
+            header.col(|ui| {
+                ui.label("Key");
+            });
+            header.col(|ui| {
+                ui.label("Value");
+            });
+            header.col(|ui| {
+                ui.label("");
+            });
+        })
+        .body(|mut body| {
+            vars.retain_mut(|el| {
+                let mut is_retain = true;
+                body.row(30.0, |mut row| {
+                    row.col(|ui| {
+                        let mut enabled = !el.disable;
+                        if ui.checkbox(&mut enabled, "").changed() {
+                            el.disable = !enabled;
+                        }
+                    });
+
+                    row.col(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut el.key).desired_width(f32::INFINITY));
+                    });
+
+                    row.col(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut el.value).desired_width(f32::INFINITY));
+                    });
+
+                    row.col(|ui| {
+                        if error_button(ui, "Del").clicked() {
+                            is_retain = false;
+                        }
+                    });
+                });
+                is_retain
+            });
+        });
+}
+
+pub fn assertion_table(ui: &mut Ui, id: impl std::hash::Hash, assertions: &mut Vec<Assertion>) {
+    ui.horizontal(|ui| {
+        if ui.button("Add").clicked() {
+            assertions.push(Assertion::default());
+        }
+        if ui.button("Add 2xx").clicked() {
+            assertions.push(Assertion::new(AssertionKind::StatusInRange, "", "2xx"));
+        }
+    });
+
+    ui.separator();
+
+    egui::ScrollArea::vertical()
+        .id_salt(&id)
+        .max_height(200.0)
+        .show(ui, |ui| {
+            egui_extras::TableBuilder::new(ui)
+                .id_salt(&id)
+                .striped(true)
+                .resizable(true)
+                .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                .column(egui_extras::Column::auto())
+                .column(egui_extras::Column::initial(150.0).at_least(100.0))
+                .column(egui_extras::Column::initial(COLUMN_WIDTH_INITIAL).range(100.0..=400.0))
+                .column(egui_extras::Column::initial(COLUMN_WIDTH_INITIAL).range(100.0..=400.0))
+                .column(egui_extras::Column::initial(100.0).at_least(40.0).at_most(400.0))
+                .min_scrolled_height(10.0)
+                .header(20.0, |mut header| {
+                    header.col(|ui| {
+                        ui.strong("");
+                    });
+                    header.col(|ui| {
+                        ui.strong("Kind");
+                    });
+                    header.col(|ui| {
+                        ui.strong("Target");
+                    });
+                    header.col(|ui| {
+                        ui.strong("Expected");
+                    });
+                })
+                .body(|mut body| {
+                    let mut index = 0;
+                    assertions.retain_mut(|el| {
+                        let mut is_retain = true;
+                        index += 1;
+
+                        body.row(30.0, |mut row| {
+                            row.col(|ui| {
+                                ui.checkbox(&mut el.disable, "");
+                            });
+
+                            row.col(|ui| {
+                                egui::ComboBox::from_id_salt(("assertion kind", index))
+                                    .selected_text(el.kind.as_ref())
+                                    .show_ui(ui, |ui| {
+                                        for kind in &ASSERTION_KINDS {
+                                            ui.selectable_value(&mut el.kind, kind.to_owned(), kind.as_ref());
+                                        }
+                                    });
+                            });
+
+                            row.col(|ui| {
+                                let enabled = matches!(
+                                    el.kind,
+                                    AssertionKind::HeaderExists
+                                        | AssertionKind::HeaderMatches
+                                        | AssertionKind::JsonPathEquals
+                                );
+                                ui.add_enabled(
+                                    enabled,
+                                    egui::TextEdit::singleline(&mut el.target)
+                                        .hint_text("header / $.data.id")
+                                        .desired_width(f32::INFINITY),
+                                );
+                            });
+
+                            row.col(|ui| {
+                                ui.add_enabled(
+                                    el.kind != AssertionKind::HeaderExists,
+                                    egui::TextEdit::singleline(&mut el.value)
+                                        .desired_width(f32::INFINITY),
+                                );
+                            });
+
+                            row.col(|ui| {
+                                if error_button(ui, "Del").clicked() {
+                                    is_retain = false;
+                                }
+                            });
+                        });
+                        is_retain
+                    });
+                });
+        });
+}
+
+/// 多阶段负载编辑, 每个阶段从上一阶段的目标线性变化到本阶段的目标
+pub fn load_stages_table(ui: &mut Ui, id: impl std::hash::Hash, profile: &mut LoadProfile) {
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt(("profile kind", &id))
+            .selected_text(profile.kind.as_ref())
+            .show_ui(ui, |ui| {
+                for kind in &PROFILE_KINDS {
+                    ui.selectable_value(&mut profile.kind, kind.to_owned(), kind.as_ref());
+                }
+            });
+        if ui.button("Add").clicked() {
+            let target = profile.stages.last().map(|s| s.target).unwrap_or(10.0);
+            profile.stages.push(LoadStage { duration: 30.0, target });
+        }
+        ui.label(format!("总时长: {:.0}s", profile.total_duration()));
+    });
+
+    let unit = match profile.kind {
+        ProfileKind::Concurrency => "并发",
+        ProfileKind::Rate => "RPS",
+    };
+
+    egui_extras::TableBuilder::new(ui)
+        .id_salt(&id)
+        .striped(true)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(egui_extras::Column::auto())
+        .column(egui_extras::Column::initial(120.0))
+        .column(egui_extras::Column::initial(120.0))
+        .column(egui_extras::Column::auto())
+        .header(20.0, |mut header| {
+            header.col(|ui| {
+                ui.strong("#");
+            });
+            header.col(|ui| {
+                ui.strong("Duration (s)");
+            });
+            header.col(|ui| {
+                ui.strong(format!("Target ({})", unit));
+            });
+        })
+        .body(|mut body| {
+            let mut index = 0;
+            profile.stages.retain_mut(|stage| {
+                let mut is_retain = true;
+                index += 1;
+                body.row(26.0, |mut row| {
+                    row.col(|ui| {
+                        ui.label(index.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.add(egui::DragValue::new(&mut stage.duration).range(0.0..=load::MAX_DURATION_SECS).speed(1.0));
+                    });
+                    row.col(|ui| {
+                        ui.add(egui::DragValue::new(&mut stage.target).range(0.0..=load::MAX_STAGE_TARGET).speed(1.0));
+                    });
+                    row.col(|ui| {
+                        if error_button(ui, "Del").clicked() {
+                            is_retain = false;
+                        }
+                    });
+                });
+                is_retain
+            });
+        });
+}
+
+/// 每个阶段的请求数, QPS 和延迟, 当前阶段高亮
+pub fn stage_stats_table(ui: &mut Ui, stage_stats: &[RequestStats], profile: &LoadProfile, active: Option<usize>) {
+    egui_extras::TableBuilder::new(ui)
+        .id_salt("stage stats")
+        .striped(true)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .columns(egui_extras::Column::auto().at_least(60.0), 8)
+        .header(20.0, |mut header| {
+            for title in ["阶段", "目标", "请求数", "失败", "QPS", "Avg", "P95", "P99"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|mut body| {
+            for (i, stats) in stage_stats.iter().enumerate() {
+                body.row(22.0, |mut row| {
+                    let ms = |v: Option<f64>| v.map(|v| format!("{:.2} ms", v)).unwrap_or_else(|| "-".to_owned());
+                    let cells = [
+                        format!("{}", i + 1),
+                        profile.stages.get(i).map(|s| format!("{:.0}", s.target)).unwrap_or_default(),
+                        stats.total_requests().to_string(),
+                        stats.failed.to_string(),
+                        stats.stage_qps().map(|q| format!("{:.0}", q)).unwrap_or_else(|| "-".to_owned()),
+                        ms(stats.avg_response_time()),
+                        ms(stats.percentile(95.0)),
+                        ms(stats.percentile(99.0)),
+                    ];
+                    for cell in cells {
+                        row.col(|ui| {
+                            let text = RichText::new(cell);
+                            ui.label(if active == Some(i) {
+                                text.strong().color(Color32::from_rgb(0, 150, 255))
+                            } else {
+                                text
+                            });
+                        });
+                    }
+                });
+            }
+        });
+}
+
+/// Count / Duration 模式的并发数输入框
+pub fn concurrency_input(ui: &mut Ui, concurrency: &mut String) -> Response {
+    ui.add(
+        egui::TextEdit::singleline(concurrency)
+            .desired_width(60.)
+            .hint_text("VUs"),
+    )
+    .on_hover_text(format!(
+        "并发数 (同时在途的请求数), 为空时为 {}",
+        api_test_rs::load::DEFAULT_CONCURRENCY
+    ))
+}
+
+pub fn assertion_results(ui: &mut Ui, results: &[AssertionResult]) {
+    let passed = results.iter().filter(|r| r.passed).count();
+    ui.label(format!("通过 {} / {}", passed, results.len()));
+    ui.separator();
+
+    for result in results {
+        ui.horizontal(|ui| {
+            if result.passed {
+                ui.colored_label(Color32::GREEN, "✔");
+            } else {
+                ui.colored_label(Color32::RED, "✖");
+            }
+            ui.monospace(&result.name);
+            ui.label(RichText::new(&result.message).weak());
+        });
+    }
+}
+
+/// 项目/组级别脚本的编辑框
+pub fn shared_scripts_editor(ui: &mut Ui, id: impl std::hash::Hash + Copy, scripts: &mut SharedScripts) {
+    for (title, script) in [
+        ("Pre-Request Script (请求前脚本):", &mut scripts.pre_request_script),
+        ("Post-Response Script (响应后脚本):", &mut scripts.post_response_script),
+    ] {
+        ui.label(title);
+        ui.add(
+            egui::TextEdit::multiline(script)
+                .id_salt((id, title))
+                .font(egui::TextStyle::Monospace)
+                .code_editor()
+                .desired_rows(4)
+                .desired_width(f32::INFINITY),
+        );
+    }
+}
+
+/// 脚本耗时和 console_log 输出, 错误标红
+pub fn console_output(
+    ui: &mut Ui,
+    lines: &[ConsoleLine],
+    pre_script_time: Option<std::time::Duration>,
+    post_script_time: Option<std::time::Duration>,
+) {
+    let time = |t: Option<std::time::Duration>| {
+        t.map(|t| format!("{:.2} ms", t.as_secs_f64() * 1000.0))
+            .unwrap_or_else(|| "-".to_owned())
+    };
+    ui.label(format!(
+        "Pre-request: {}  Post-response: {}",
+        time(pre_script_time),
+        time(post_script_time)
+    ));
+    ui.separator();
+
+    if lines.is_empty() {
+        ui.label(RichText::new("无输出").weak());
+        return;
+    }
+    for line in lines {
+        ui.horizontal_top(|ui| {
+            let source = match line.scope {
+                ScriptScope::Request => line.phase.as_ref().to_owned(),
+                scope => format!("{} {}", scope.as_ref(), line.phase.as_ref()),
+            };
+            ui.label(RichText::new(format!("[{}]", source)).weak());
+            let text = RichText::new(&line.message).monospace();
+            match line.level {
+                ConsoleLevel::Log => ui.label(text),
+                ConsoleLevel::Error => ui.label(text.color(Color32::RED)),
+            };
+        });
+    }
+}
+
+/// 状态码和错误类型分布
+pub fn breakdown_tables(ui: &mut Ui, stats: &RequestStats) {
+    let total = stats.total_requests().max(1) as f64;
+    if !stats.status_codes.is_empty() {
+        egui_extras::TableBuilder::new(ui)
+            .id_salt("status code stats")
+            .striped(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .columns(egui_extras::Column::auto().at_least(80.0), 3)
+            .header(20.0, |mut header| {
+                for title in ["状态码", "次数", "占比"] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|mut body| {
+                for (code, count) in &stats.status_codes {
+                    body.row(20.0, |mut row| {
+                        row.col(|ui| {
+                            let reason = reqwest::StatusCode::from_u16(*code)
+                                .ok()
+                                .and_then(|s| s.canonical_reason())
+                                .unwrap_or_default();
+                            let text = format!("{} {}", code, reason);
+                            if *code < 400 {
+                                ui.label(text);
+                            } else {
+                                ui.colored_label(Color32::RED, text);
+                            }
+                        });
+                        row.col(|ui| {
+                            ui.label(count.to_string());
+                        });
+                        row.col(|ui| {
+                            ui.label(format!("{:.2}%", *count as f64 / total * 100.0));
+                        });
+                    });
+                }
+            });
+    }
+
+    if !stats.errors.is_empty() {
+        ui.add_space(5.0);
+        egui_extras::TableBuilder::new(ui)
+            .id_salt("error kind stats")
+            .striped(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(egui_extras::Column::auto().at_least(120.0))
+            .column(egui_extras::Column::auto().at_least(80.0))
+            .column(egui_extras::Column::remainder())
+            .header(20.0, |mut header| {
+                for title in ["错误类型", "次数", "示例"] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|mut body| {
+                for (kind, summary) in &stats.errors {
+                    let height = 20.0 * summary.samples.len().max(1) as f32;
+                    body.row(height, |mut row| {
+                        row.col(|ui| {
+                            ui.colored_label(Color32::RED, kind.as_ref());
+                        });
+                        row.col(|ui| {
+                            ui.label(summary.count.to_string());
+                        });
+                        row.col(|ui| {
+                            ui.vertical(|ui| {
+                                for sample in &summary.samples {
+                                    ui.add(egui::Label::new(sample).truncate()).on_hover_text(sample);
+                                }
+                            });
+                        });
+                    });
+                }
+            });
+    }
+}
+
+/// 数据文件预览, 最多显示前 100 行
+pub fn data_rows_table(ui: &mut Ui, rows: &[Vec<PairUi>]) {
+    let Some(first) = rows.first() else {
+        return;
+    };
+    ui.label(format!("共 {} 行", rows.len()));
+    let columns: Vec<&str> = first.iter().map(|v| v.key.as_str()).collect();
+    egui::ScrollArea::both().id_salt("data rows scroll").show(ui, |ui| {
+        egui_extras::TableBuilder::new(ui)
+            .id_salt("data rows")
+            .striped(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(egui_extras::Column::auto().at_least(40.0))
+            .columns(egui_extras::Column::auto().at_least(80.0), columns.len())
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.strong("#");
+                });
+                for column in &columns {
+                    header.col(|ui| {
+                        ui.strong(*column);
+                    });
+                }
+            })
+            .body(|mut body| {
+                for (i, row) in rows.iter().take(100).enumerate() {
+                    body.row(20.0, |mut table_row| {
+                        table_row.col(|ui| {
+                            ui.label((i + 1).to_string());
+                        });
+                        for column in &columns {
+                            table_row.col(|ui| {
+                                let value = row.iter().find(|v| v.key == *column).map(|v| v.value.as_str());
+                                ui.label(value.unwrap_or_default());
+                            });
+                        }
+                    });
+                }
+            });
+    });
+}
+
+/// Data 模式中每行数据的结果
+pub fn iteration_table(ui: &mut Ui, iterations: &[IterationResult]) {
+    let passed = iterations.iter().filter(|i| i.passed()).count();
+    ui.label(format!("通过: {}  失败: {}", passed, iterations.len() - passed));
+    egui_extras::TableBuilder::new(ui)
+        .id_salt("iterations")
+        .striped(true)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(egui_extras::Column::auto().at_least(40.0))
+        .column(egui_extras::Column::auto().at_least(50.0))
+        .column(egui_extras::Column::auto().at_least(60.0))
+        .column(egui_extras::Column::auto().at_least(70.0))
+        .column(egui_extras::Column::auto().at_least(200.0))
+        .column(egui_extras::Column::remainder())
+        .header(20.0, |mut header| {
+            for title in ["行", "结果", "状态", "耗时", "变量", "信息"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(20.0, iterations.len(), |mut row| {
+                let iteration = &iterations[row.index()];
+                row.col(|ui| {
+                    ui.label((iteration.row + 1).to_string());
+                });
+                row.col(|ui| {
+                    if iteration.passed() {
+                        ui.colored_label(Color32::GREEN, "PASS");
+                    } else {
+                        ui.colored_label(Color32::RED, "FAIL");
+                    }
+                });
+                row.col(|ui| {
+                    ui.label(iteration.status.map(|s| s.to_string()).unwrap_or_else(|| "-".to_owned()));
+                });
+                row.col(|ui| {
+                    ui.label(format!("{} ms", iteration.duration));
+                });
+                row.col(|ui| {
+                    let vars = iteration
+                        .vars
+                        .iter()
+                        .map(|v| format!("{}={}", v.key, v.value))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    ui.add(egui::Label::new(&vars).truncate()).on_hover_text(&vars);
+                });
+                row.col(|ui| {
+                    let message = match &iteration.error {
+                        Some(err) => err.to_owned(),
+                        None => iteration
+                            .assertions
+                            .iter()
+                            .filter(|r| !r.passed)
+                            .map(|r| format!("{}: {}", r.name, r.message))
+                            .collect::<Vec<_>>()
+                            .join("; "),
+                    };
+                    ui.add(egui::Label::new(&message).truncate()).on_hover_text(&message);
+                });
+            });
+        });
+}
+
+/// 与基线的对比, 退化的指标标红
+pub fn baseline_table(ui: &mut Ui, diffs: &[MetricDiff]) {
+    let value = |v: Option<f64>| v.map(|v| format!("{:.2}", v)).unwrap_or_else(|| "-".to_owned());
+    egui_extras::TableBuilder::new(ui)
+        .id_salt("baseline diff")
+        .striped(true)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .columns(egui_extras::Column::auto().at_least(90.0), 4)
+        .header(20.0, |mut header| {
+            for title in ["指标", "基线", "本次", "变化"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|mut body| {
+            for diff in diffs {
+                body.row(20.0, |mut row| {
+                    let cells = [diff.name.to_owned(), value(diff.baseline), value(diff.current), diff.change_text()];
+                    for cell in cells {
+                        row.col(|ui| {
+                            if diff.regressed {
+                                ui.colored_label(Color32::RED, cell);
+                            } else {
+                                ui.label(cell);
+                            }
+                        });
+                    }
+                });
+            }
+        });
+}
+
+/// 每秒统计折线图, 进行中时不显示未结束的一秒
+pub fn timeline_charts(ui: &mut Ui, timeline: &Timeline, running: bool) {
+    let len = if running { timeline.len().saturating_sub(1) } else { timeline.len() };
+    if len == 0 {
+        ui.label("等待第一秒的数据...");
+        return;
+    }
+    let buckets = &timeline.buckets()[..len];
+    let series = |f: fn(&TimelineBucket) -> f64| buckets.iter().map(f).collect::<Vec<_>>();
+
+    line_chart(ui, "QPS", "", &[("QPS", Color32::from_rgb(0, 150, 255), series(|b| b.requests as f64))]);
+    let latency = |i: usize| {
+        (0..len)
+            .map(|s| timeline.latency(s)[i].unwrap_or(f64::NAN))
+            .collect::<Vec<_>>()
+    };
+    line_chart(
+        ui,
+        "响应时间",
+        "ms",
+        &[
+            ("P50", Color32::from_rgb(0, 180, 0), latency(0)),
+            ("P95", Color32::from_rgb(230, 160, 0), latency(1)),
+            ("P99", Color32::from_rgb(220, 50, 50), latency(2)),
+        ],
+    );
+    line_chart(ui, "错误", "/s", &[("错误", Color32::RED, series(|b| b.errors as f64))]);
+    line_chart(
+        ui,
+        "流量",
+        "KB/s",
+        &[
+            ("上传", Color32::from_rgb(150, 100, 255), series(|b| b.upload_bytes as f64 / 1024.0)),
+            ("下载", Color32::from_rgb(0, 180, 180), series(|b| b.download_bytes as f64 / 1024.0)),
+        ],
+    );
+}
+
+/// 折线图, 第 i 个值对应第 i 秒, NaN 表示无数据
+pub fn line_chart(ui: &mut Ui, title: &str, unit: &str, series: &[(&str, Color32, Vec<f64>)]) {
+    ui.horizontal(|ui| {
+        ui.strong(title);
+        for (name, color, _) in series {
+            ui.colored_label(*color, format!("━ {}", name));
+        }
+    });
+
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(ui.available_width(), 100.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let text_color = ui.visuals().weak_text_color();
+    painter.rect_stroke(
+        rect,
+        2.0,
+        ui.visuals().widgets.noninteractive.bg_stroke,
+        egui::StrokeKind::Inside,
+    );
+
+    let len = series.iter().map(|(_, _, values)| values.len()).max().unwrap_or(0);
+    if len == 0 {
+        return;
+    }
+    let max = series
+        .iter()
+        .flat_map(|(_, _, values)| values.iter())
+        .copied()
+        .filter(|v| v.is_finite())
+        .fold(0.0, f64::max);
+    let max = if max > 0.0 { max * 1.1 } else { 1.0 };
+
+    let plot = rect.shrink2(egui::vec2(4.0, 14.0));
+    let x = |i: usize| plot.left() + plot.width() * i as f32 / (len - 1).max(1) as f32;
+    let y = |v: f64| plot.bottom() - plot.height() * (v / max) as f32;
+    for (_, color, values) in series {
+        // NaN 处断开
+        let mut line = Vec::new();
+        for (i, v) in values.iter().enumerate() {
+            if v.is_finite() {
+                line.push(egui::pos2(x(i), y(*v)));
+            } else if !line.is_empty() {
+                painter.add(egui::Shape::line(std::mem::take(&mut line), egui::Stroke::new(1.5, *color)));
+            }
+        }
+        if line.len() == 1 {
+            painter.circle_filled(line[0], 2.0, *color);
+        } else if !line.is_empty() {
+            painter.add(egui::Shape::line(line, egui::Stroke::new(1.5, *color)));
+        }
+    }
+
+    let font = egui::FontId::proportional(10.0);
+    let label = format!("{:.2} {}", max, unit);
+    painter.text(rect.left_top() + egui::vec2(4.0, 2.0), egui::Align2::LEFT_TOP, label, font.clone(), text_color);
+    painter.text(rect.left_bottom() + egui::vec2(4.0, -2.0), egui::Align2::LEFT_BOTTOM, "0s", font.clone(), text_color);
+    let end = format!("{}s", len - 1);
+    painter.text(rect.right_bottom() + egui::vec2(-4.0, -2.0), egui::Align2::RIGHT_BOTTOM, end, font, text_color);
+
+    if let Some(pos) = response.hover_pos() {
+        let i = (((pos.x - plot.left()) / plot.width() * (len - 1) as f32).round().max(0.0) as usize).min(len - 1);
+        painter.vline(x(i), plot.y_range(), egui::Stroke::new(1.0, text_color));
+        let values = series
+            .iter()
+            .map(|(name, _, values)| match values.get(i).filter(|v| v.is_finite()) {
+                Some(v) => format!("{}: {:.2} {}", name, v, unit),
+                None => format!("{}: -", name),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        response.on_hover_text(format!("{}s\n{}", i, values));
+    }
+}
+
 pub fn horizontal_tabs<T>(ui: &mut Ui, tabs: std::slice::Iter<T>, current_value: &mut T)
 where
     T: Clone + PartialEq + AsRef<str> + ?Sized,